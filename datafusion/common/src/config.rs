@@ -284,6 +284,12 @@ config_namespace! {
         /// batches and merged.
         pub sort_in_place_threshold_bytes: usize, default = 1024 * 1024
 
+        /// Number of batches to buffer ahead for each input partition of a
+        /// `SortPreservingMergeExec` introduced by the optimizer in place of an
+        /// order-losing operator. Higher values reduce merge stalls at the cost
+        /// of memory
+        pub sort_preserving_merge_batch_prefetch: usize, default = 1
+
         /// Number of files to read in parallel when inferring schema and statistics
         pub meta_fetch_concurrency: usize, default = 32
 
@@ -575,6 +581,84 @@ config_namespace! {
         /// `RepartitionExec` even if this requires subsequently resorting data using a `SortExec`.
         pub prefer_existing_sort: bool, default = false
 
+        /// When set to true, operators are replaced by their order-preserving variants to
+        /// remove a `SortExec` in bounded plans, even if `prefer_existing_sort` is false, as
+        /// long as this has no extra runtime cost; i.e. every replaced operator has a single
+        /// input partition, so that the order-preserving variants do not need to merge
+        pub prefer_existing_sort_when_free: bool, default = false
+
+        /// When set to true, failing to construct an order-preserving variant of an
+        /// operator (i.e. a sort-preserving `RepartitionExec` or a `SortPreservingMergeExec`)
+        /// keeps the original operators, along with any `SortExec` above them, and the
+        /// rest of the plan is still optimized. When set to false, such failures cause
+        /// the query to fail
+        pub order_preserving_best_effort: bool, default = false
+
+        /// When set to true, a `SortExec` on top of a `CoalescePartitionsExec` with
+        /// unordered input is replaced by a `SortExec` that sorts each partition,
+        /// followed by a `SortPreservingMergeExec`, so that sorting runs in parallel
+        pub parallelize_sort: bool, default = false
+
+        /// When set to true, round-robin `RepartitionExec`s may be replaced by their
+        /// order-preserving variants to remove a `SortExec`, like other `RepartitionExec`s.
+        /// When set to false, round-robin repartitions are never converted, and a
+        /// `SortExec` that could only be removed by converting one is kept. Note that
+        /// round-robin repartitions were always converted before this option was added,
+        /// so set it to true to keep the previous plans
+        pub allow_order_preserving_round_robin: bool, default = false
+
+        /// When set to true, order-preserving `RepartitionExec`s introduced to remove a
+        /// `SortExec` are displayed as `SortPreservingRepartitionExec` instead of
+        /// `RepartitionExec` with `preserve_order=true`. The operators are still
+        /// `RepartitionExec`s with the `preserve_order` flag set
+        pub emit_distinct_preserve_order_type: bool, default = false
+
+        /// Bias between 0.0 and 1.0 used to weigh, via the estimated costs, the merges
+        /// introduced to remove a `SortExec` against the per-partition sorts they replace
+        /// in bounded plans. At 0.0 such conversions are always made (favoring fewer
+        /// sorts), while higher values increasingly favor keeping the parallel sorts over
+        /// the single-threaded merging work of the order-preserving variants
+        pub order_preserving_parallelism_bias: f64, default = 0.0
+
+        /// Maximum number of sorted streams that a merge introduced to remove a `SortExec`
+        /// (i.e. a `SortPreservingMergeExec` or a sort-preserving `RepartitionExec`) may
+        /// merge, as each merged stream keeps a batch buffered. In bounded plans, such
+        /// conversions are declined and the `SortExec` is kept if they exceed this budget,
+        /// while in unbounded plans the merges are split into a cascade of merges within
+        /// the budget. Values below 2 are treated as 2. When not set, the fan-in is unlimited
+        pub max_order_preserving_merge_fanin: Option<usize>, default = None
+
+        /// When set to true, a `SortPreservingMergeExec` introduced at the root of the plan
+        /// to remove a `SortExec` is wrapped in a `CoalesceBatchesExec`, so that consumers
+        /// of the single result stream receive batches of `execution.batch_size` rows
+        pub coalesce_final_merge_output: bool, default = false
+
+        /// Batch size of the streaming merge of a sort-preserving `RepartitionExec`
+        /// introduced to remove a `SortExec`. Smaller batches reduce the peak memory of
+        /// the merge, e.g. for wide schemas. When not set, `execution.batch_size` is used
+        pub preserve_order_repartition_batch_size: Option<usize>, default = None
+
+        /// When set to true, a round-robin `RepartitionExec` with a single input partition
+        /// is removed from below a hash `RepartitionExec` that preserves ordering to remove
+        /// a `SortExec`. The hash repartition then reads the single ordered input directly,
+        /// so that it does not need to merge its input streams
+        pub remove_round_robin_below_ordered_repartition: bool, default = false
+
+        /// When set to true, the `SortPreservingMergeExec`s and order-preserving
+        /// `RepartitionExec`s introduced to remove a `SortExec` are annotated with
+        /// `(order-preserving-opt)` in the displayed plan, to tell them apart from the
+        /// ones that were already in the plan
+        pub annotate_order_preserving_variants: bool, default = false
+
+        /// When set to false, `RepartitionExec`s are never replaced by their order-preserving
+        /// variants to remove a `SortExec`, while `CoalescePartitionsExec`s still may be
+        pub enable_order_preserving_repartition: bool, default = true
+
+        /// When set to false, `CoalescePartitionsExec`s are never replaced by
+        /// `SortPreservingMergeExec`s to remove a `SortExec`, while `RepartitionExec`s
+        /// still may be replaced by their order-preserving variants
+        pub enable_order_preserving_coalesce: bool, default = true
+
         /// When set to true, the logical plan optimizer will produce warning
         /// messages if any optimization rules produce errors and then proceed to the next
         /// rule. When set to false, any rules that produce errors will cause the query to fail
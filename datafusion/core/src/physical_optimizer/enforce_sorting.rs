@@ -41,7 +41,8 @@ use super::utils::{add_sort_above, add_sort_above_with_check};
 use crate::config::ConfigOptions;
 use crate::error::Result;
 use crate::physical_optimizer::replace_with_order_preserving_variants::{
-    replace_with_order_preserving_variants, OrderPreservationContext,
+    coalesce_final_merge_output, replace_with_order_preserving_variants,
+    OrderPreservationContext,
 };
 use crate::physical_optimizer::sort_pushdown::{
     assign_initial_requirements, pushdown_sorts, SortPushDown,
@@ -174,7 +175,8 @@ impl PhysicalOptimizerRule for EnforceSorting {
             adjusted.plan
         };
 
-        let plan_with_pipeline_fixer = OrderPreservationContext::new_default(new_plan);
+        let plan_with_pipeline_fixer =
+            OrderPreservationContext::new_default(Arc::clone(&new_plan));
         let updated_plan = plan_with_pipeline_fixer
             .transform_up(|plan_with_pipeline_fixer| {
                 replace_with_order_preserving_variants(
@@ -185,10 +187,12 @@ impl PhysicalOptimizerRule for EnforceSorting {
                 )
             })
             .data()?;
+        let updated_plan =
+            coalesce_final_merge_output(&new_plan, updated_plan.plan, config);
 
         // Execute a top-down traversal to exploit sort push-down opportunities
         // missed by the bottom-up traversal:
-        let mut sort_pushdown = SortPushDown::new_default(updated_plan.plan);
+        let mut sort_pushdown = SortPushDown::new_default(updated_plan);
         assign_initial_requirements(&mut sort_pushdown);
         let adjusted = pushdown_sorts(sort_pushdown)?;
 
@@ -19,30 +19,141 @@
 //! order-preserving variants when it is helpful; either in terms of
 //! performance or to accommodate unbounded streams by fixing the pipeline.
 
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
 
 use super::utils::{is_repartition, is_sort_preserving_merge};
 use crate::error::Result;
-use crate::physical_optimizer::utils::{is_coalesce_partitions, is_sort};
+use crate::physical_optimizer::utils::{
+    is_coalesce_partitions, is_interleave, is_sort, is_union,
+};
+use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+use crate::physical_plan::insert::DataSinkExec;
+use crate::physical_plan::joins::HashJoinExec;
 use crate::physical_plan::repartition::RepartitionExec;
-use crate::physical_plan::sorts::sort_preserving_merge::SortPreservingMergeExec;
+use crate::physical_plan::sorts::sort::SortExec;
+use crate::physical_plan::sorts::sort_preserving_merge::{
+    GroupedSortPreservingMergeExec, SortPreservingMergeExec,
+};
+use crate::physical_plan::union::UnionExec;
+use crate::physical_plan::{displayable, Distribution, ExecutionPlan, Partitioning};
 
 use datafusion_common::config::ConfigOptions;
-use datafusion_common::tree_node::Transformed;
+use datafusion_common::instant::Instant;
+use datafusion_common::stats::Precision;
+use datafusion_common::tree_node::{
+    Transformed, TransformedResult, TreeNode, TreeNodeRecursion,
+};
+use datafusion_common::{internal_err, Statistics};
+use datafusion_expr::Volatility;
+use datafusion_physical_expr::equivalence::collapse_lex_req;
+use datafusion_physical_expr::{
+    physical_exprs_contains, EquivalenceProperties, PhysicalExpr, PhysicalSortExpr,
+    PhysicalSortRequirement, ScalarFunctionExpr,
+};
 use datafusion_physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion_physical_plan::empty::EmptyExec;
+use datafusion_physical_plan::placeholder_row::PlaceholderRowExec;
+use datafusion_physical_plan::sorts::partial_sort::PartialSortExec;
 use datafusion_physical_plan::tree_node::PlanContext;
 use datafusion_physical_plan::ExecutionPlanProperties;
 
+use datafusion_physical_optimizer::PhysicalOptimizerRule;
 use itertools::izip;
+use log::{debug, log_enabled, Level};
 
 /// For a given `plan`, this object carries the information one needs from its
 /// descendants to decide whether it is beneficial to replace order-losing (but
 /// somewhat faster) variants of certain operators with their order-preserving
 /// (but somewhat slower) cousins.
-pub type OrderPreservationContext = PlanContext<bool>;
+///
+/// The data of a node is `Some(ordering)` if the node is connected to an
+/// operator that loses ordering below it, where `ordering` is the exact
+/// ordering of the input of the operator that initiated the connection (i.e.
+/// the ordering its order-preserving variant would keep). It is `None` if there
+/// is no such connection.
+pub type OrderPreservationContext = PlanContext<Option<Vec<PhysicalSortExpr>>>;
+
+/// Checks whether the child at the given index is the build side of a
+/// [`HashJoinExec`]. The build side is fully consumed to build the hash table,
+/// so its ordering is irrelevant to the output of the join and converting any
+/// operator in it to an order-preserving variant would be pure waste.
+fn is_hash_join_build_side(plan: &Arc<dyn ExecutionPlan>, idx: usize) -> bool {
+    idx == 0 && plan.as_any().is::<HashJoinExec>()
+}
+
+/// Returns the minimal merge key that is equivalent to the given `exprs` under
+/// the given equivalence properties of the merged input. Expressions that are
+/// constant across all partitions are dropped, and expressions that are equal
+/// to an earlier expression in the key are collapsed into it. If nothing is
+/// left (i.e. the whole key is constant), the given `exprs` are returned as is.
+pub fn minimal_ordering(
+    exprs: &[PhysicalSortExpr],
+    eq_properties: &EquivalenceProperties,
+) -> Vec<PhysicalSortExpr> {
+    let eq_group = eq_properties.eq_group();
+    // Constants within a single partition may still differ between partitions,
+    // so only the ones that hold across partitions can be dropped from a key
+    // that merges partitions:
+    let constants = eq_group.normalize_exprs(
+        eq_properties
+            .constants()
+            .iter()
+            .filter(|constant| constant.across_partitions())
+            .map(|constant| Arc::clone(constant.expr())),
+    );
+    let requirements = PhysicalSortRequirement::from_sort_exprs(exprs);
+    let minimal = collapse_lex_req(
+        eq_group
+            .normalize_sort_requirements(&requirements)
+            .into_iter()
+            .filter(|requirement| !physical_exprs_contains(&constants, &requirement.expr))
+            .collect(),
+    );
+    if minimal.is_empty() {
+        exprs.to_vec()
+    } else {
+        PhysicalSortRequirement::to_sort_exprs(minimal)
+    }
+}
+
+/// Removes the given node if it is a [`SortExec`] whose input already
+/// satisfies its ordering with the same partitioning. Otherwise, returns the
+/// node as is.
+fn remove_redundant_sort(mut opc: OrderPreservationContext) -> OrderPreservationContext {
+    if let Some(sort) = opc.plan.as_any().downcast_ref::<SortExec>() {
+        let input = sort.input();
+        if sort.fetch().is_none()
+            && (sort.preserve_partitioning()
+                || input.output_partitioning().partition_count() <= 1)
+            && input.equivalence_properties().ordering_satisfy(sort.expr())
+        {
+            return opc.children.swap_remove(0);
+        }
+    }
+    opc
+}
+
+/// Checks whether the given plan is a round-robin `RepartitionExec`. Unless
+/// `optimizer.allow_order_preserving_round_robin` is set, such repartitions are
+/// never replaced by their order-preserving variants.
+fn is_round_robin_repartition(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    is_repartition(plan)
+        && matches!(plan.output_partitioning(), Partitioning::RoundRobinBatch(_))
+}
 
 /// Updates order-preservation data for all children of the given node.
 pub fn update_children(opc: &mut OrderPreservationContext) {
+    update_children_with_hints(opc, &NoHints)
+}
+
+/// Same as [`update_children`], but describes the nodes with the given `hints`.
+fn update_children_with_hints(
+    opc: &mut OrderPreservationContext,
+    hints: &dyn OrderPreservingHints,
+) {
     for PlanContext {
         plan,
         children,
@@ -50,46 +161,777 @@ pub fn update_children(opc: &mut OrderPreservationContext) {
     } in opc.children.iter_mut()
     {
         let maintains_input_order = plan.maintains_input_order();
+        // Orderings can not be preserved through nodes that route rows to
+        // their partitions non-deterministically:
         let inspect_child = |idx| {
-            maintains_input_order[idx]
-                || is_coalesce_partitions(plan)
-                || is_repartition(plan)
+            !is_hash_join_build_side(plan, idx)
+                && hints.has_deterministic_partitioning(plan.as_ref())
+                && (maintains_input_order[idx]
+                    || is_coalesce_partitions(plan)
+                    || is_repartition(plan)
+                    || is_interleave(plan))
         };
 
         // We cut the path towards nodes that do not maintain ordering.
         for (idx, c) in children.iter_mut().enumerate() {
-            c.data &= inspect_child(idx);
+            if !inspect_child(idx) {
+                c.data = None;
+            }
         }
 
         let plan_children = plan.children();
         *data = if plan_children.is_empty() {
-            false
-        } else if !children[0].data
+            None
+        } else if children[0].data.is_none()
             && ((is_repartition(plan) && !maintains_input_order[0])
                 || (is_coalesce_partitions(plan)
-                    && plan_children[0].output_ordering().is_some()))
+                    && plan_children[0].output_ordering().is_some())
+                || (is_interleave(plan)
+                    && plan_children
+                        .iter()
+                        .all(|child| child.output_ordering().is_some()))
+                || is_homogeneously_ordered_union(plan))
         {
-            // We either have a RepartitionExec or a CoalescePartitionsExec
-            // and they lose their input ordering, so initiate connection:
-            true
+            // We either have a RepartitionExec, a CoalescePartitionsExec or an
+            // InterleaveExec and they lose their input ordering, or a UnionExec
+            // whose partitions can be merged into its common input ordering, so
+            // initiate connection with the ordering that can be preserved:
+            let ordering = if is_homogeneously_ordered_union(plan) {
+                plan.output_ordering()
+            } else {
+                plan_children[0].output_ordering()
+            };
+            Some(ordering.map(<[_]>::to_vec).unwrap_or_default())
         } else {
             // Maintain connection if there is a child with a connection,
             // and operator can possibly maintain that connection (either
             // in its current form or when we replace it with the corresponding
-            // order preserving operator).
+            // order preserving operator). Siblings may carry different
+            // orderings; the first connected child determines the ordering.
             children
                 .iter()
                 .enumerate()
-                .any(|(idx, c)| c.data && inspect_child(idx))
+                .find_map(|(idx, c)| c.data.clone().filter(|_| inspect_child(idx)))
+        }
+    }
+    opc.data = None;
+}
+
+/// Recomputes order-preservation data for the given (possibly already
+/// optimized) plan, so that the sub-rule can revisit its nodes.
+fn recompute_ordering_connections(
+    plan: Arc<dyn ExecutionPlan>,
+    hints: &dyn OrderPreservingHints,
+) -> OrderPreservationContext {
+    let children = plan
+        .children()
+        .into_iter()
+        .map(|child| recompute_ordering_connections(Arc::clone(child), hints))
+        .collect();
+    let mut opc = OrderPreservationContext::new(plan, None, children);
+    update_children_with_hints(&mut opc, hints);
+    opc
+}
+
+/// Renders the given plan annotated with the order-preservation data computed
+/// by [`update_children`]. For each node, `ordering_connection` shows whether
+/// the node is (finally) connected to an operator that loses ordering below it,
+/// and `children_ordering_connections` shows the connections of its children
+/// as seen when the node itself is visited.
+///
+/// This is a debugging aid that helps to understand why a connection forms or
+/// breaks at a given node.
+pub fn dump_order_connections(plan: &Arc<dyn ExecutionPlan>) -> String {
+    /// Connections of the children of a node as seen when visiting the node.
+    struct VisitedConnections {
+        connections: Vec<bool>,
+        children: Vec<VisitedConnections>,
+    }
+
+    fn visit(
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> (OrderPreservationContext, VisitedConnections) {
+        let (children, visited): (Vec<_>, Vec<_>) =
+            plan.children().into_iter().cloned().map(visit).unzip();
+        let mut opc = OrderPreservationContext::new(plan, None, children);
+        update_children(&mut opc);
+        let connections = opc.children.iter().map(|c| c.data.is_some()).collect();
+        let visited = VisitedConnections {
+            connections,
+            children: visited,
+        };
+        (opc, visited)
+    }
+
+    fn dump(
+        opc: &OrderPreservationContext,
+        visited: &VisitedConnections,
+        indent: usize,
+        output: &mut String,
+    ) {
+        let node = displayable(opc.plan.as_ref()).one_line().to_string();
+        output.push_str(&format!(
+            "{:indent$}{}, ordering_connection={}, children_ordering_connections={:?}\n",
+            "",
+            node.trim_end(),
+            opc.data.is_some(),
+            visited.connections,
+            indent = indent * 2,
+        ));
+        for (child, visited) in opc.children.iter().zip(&visited.children) {
+            dump(child, visited, indent + 1, output);
+        }
+    }
+
+    let (opc, visited) = visit(Arc::clone(plan));
+    let mut output = String::new();
+    dump(&opc, &visited, 0, &mut output);
+    output
+}
+
+/// Checks whether the given node is a [`SortPreservingMergeExec`] directly above
+/// another merge (e.g. one that replaced a [`CoalescePartitionsExec`]) whose
+/// output satisfies its ordering. As the child merge already produces a single
+/// sorted partition, returns a single merge that carries the options (fetch and
+/// prefetch) of the given merge. Otherwise, returns `None`.
+fn fuse_with_child_merge(
+    plan: &Arc<dyn ExecutionPlan>,
+) -> Option<Arc<dyn ExecutionPlan>> {
+    let merge = plan.as_any().downcast_ref::<SortPreservingMergeExec>()?;
+    let child = merge
+        .input()
+        .as_any()
+        .downcast_ref::<SortPreservingMergeExec>()?;
+    if !merge
+        .input()
+        .equivalence_properties()
+        .ordering_satisfy(merge.expr())
+    {
+        return None;
+    }
+    let fetch = match (merge.fetch(), child.fetch()) {
+        (Some(fetch), Some(child_fetch)) => Some(fetch.min(child_fetch)),
+        (fetch, child_fetch) => fetch.or(child_fetch),
+    };
+    let fused =
+        SortPreservingMergeExec::new(child.expr().to_vec(), Arc::clone(child.input()))
+            .with_fetch(fetch)
+            .with_prefetch(merge.prefetch())
+            .with_annotation(child.annotation().map(String::from));
+    Some(Arc::new(fused) as _)
+}
+
+/// Checks whether the given node is a [`SortExec`] on top of a
+/// [`CoalescePartitionsExec`] whose multi-partition input is unordered. If so,
+/// returns a plan that sorts each partition separately and merges the sorted
+/// partitions with a [`SortPreservingMergeExec`]. Otherwise, returns `None`.
+fn parallelize_sort_over_coalesce(
+    plan: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> Option<Arc<dyn ExecutionPlan>> {
+    let sort = plan.as_any().downcast_ref::<SortExec>()?;
+    let coalesce = sort
+        .input()
+        .as_any()
+        .downcast_ref::<CoalescePartitionsExec>()?;
+    let input = coalesce.input();
+    if sort.preserve_partitioning()
+        || input.output_ordering().is_some()
+        || input.output_partitioning().partition_count() <= 1
+    {
+        return None;
+    }
+    let partition_sort = SortExec::new(sort.expr().to_vec(), Arc::clone(input))
+        .with_preserve_partitioning(true)
+        .with_fetch(sort.fetch());
+    let ordering = minimal_ordering(sort.expr(), input.equivalence_properties());
+    let spm = SortPreservingMergeExec::new(ordering, Arc::new(partition_sort))
+        .with_fetch(sort.fetch())
+        .with_prefetch(config.execution.sort_preserving_merge_batch_prefetch)
+        .with_annotation(order_preserving_annotation(config));
+    Some(Arc::new(spm) as _)
+}
+
+/// Checks whether the given expression contains a volatile function (e.g.
+/// `random()`). Orderings on such expressions are not stable, so operators can
+/// not be assumed to preserve them.
+fn is_volatile(expr: &Arc<dyn PhysicalExpr>) -> bool {
+    expr.exists(|e| {
+        Ok(e.as_any()
+            .downcast_ref::<ScalarFunctionExpr>()
+            .is_some_and(|f| f.fun().signature().volatility == Volatility::Volatile))
+    })
+    .unwrap_or(false)
+}
+
+/// Checks whether the given plan is a chain of single-child operators on top
+/// of a [`PlaceholderRowExec`] or an [`EmptyExec`].
+fn is_over_placeholder_or_empty(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    match plan.children().as_slice() {
+        [] => plan.as_any().is::<PlaceholderRowExec>() || plan.as_any().is::<EmptyExec>(),
+        [child] => is_over_placeholder_or_empty(child),
+        _ => false,
+    }
+}
+
+/// Checks whether sorting the output of the given plan is expected to spill to
+/// disk; i.e. whether its statistics estimate more bytes than the memory that
+/// `execution.sort_spill_reservation_bytes` reserves for sorting.
+fn is_sort_expected_to_spill(
+    input: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> bool {
+    input
+        .statistics()
+        .ok()
+        .and_then(|statistics| statistics.total_byte_size.get_value().copied())
+        .is_some_and(|bytes| bytes > config.execution.sort_spill_reservation_bytes)
+}
+
+/// Returns the number of sorted streams merged into each output partition of
+/// the given node if it is a merge; i.e. a [`SortPreservingMergeExec`], a
+/// [`GroupedSortPreservingMergeExec`] or a sort-preserving [`RepartitionExec`].
+fn merge_fanin(plan: &Arc<dyn ExecutionPlan>) -> Option<usize> {
+    let input_partitions = || plan.children()[0].output_partitioning().partition_count();
+    if let Some(merge) = plan
+        .as_any()
+        .downcast_ref::<GroupedSortPreservingMergeExec>()
+    {
+        Some(merge.group_size().min(input_partitions()))
+    } else if is_sort_preserving_merge(plan)
+        || plan
+            .as_any()
+            .downcast_ref::<RepartitionExec>()
+            .is_some_and(|repartition| repartition.preserve_order())
+    {
+        Some(input_partitions())
+    } else {
+        None
+    }
+}
+
+/// Returns the fan-in budget of merges set by the
+/// `optimizer.max_order_preserving_merge_fanin` config option, if any.
+fn max_merge_fanin(config: &ConfigOptions) -> Option<usize> {
+    config
+        .optimizer
+        .max_order_preserving_merge_fanin
+        .map(|max_fanin| max_fanin.max(2))
+}
+
+/// Counts the merges in the given plan whose fan-in exceeds `max_fanin`.
+fn count_merges_over_fanin(plan: &Arc<dyn ExecutionPlan>, max_fanin: usize) -> usize {
+    let mut count = 0;
+    let _ = plan.apply(|node| {
+        if merge_fanin(node).is_some_and(|fanin| fanin > max_fanin) {
+            count += 1;
+        }
+        Ok(TreeNodeRecursion::Continue)
+    });
+    count
+}
+
+/// Checks whether the `alternate` plan, which replaces the `original` input of
+/// a sort, introduces merges whose fan-in exceeds the budget set by the
+/// `optimizer.max_order_preserving_merge_fanin` config option. If so, returns
+/// the fan-in of the widest merge of the `alternate` along with the budget.
+fn introduces_merges_over_fanin(
+    original: &Arc<dyn ExecutionPlan>,
+    alternate: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> Option<(usize, usize)> {
+    let max_fanin = max_merge_fanin(config)?;
+    if count_merges_over_fanin(alternate, max_fanin)
+        <= count_merges_over_fanin(original, max_fanin)
+    {
+        return None;
+    }
+    let mut widest_fanin = 0;
+    let _ = alternate.apply(|node| {
+        widest_fanin = widest_fanin.max(merge_fanin(node).unwrap_or(0));
+        Ok(TreeNodeRecursion::Continue)
+    });
+    Some((widest_fanin, max_fanin))
+}
+
+/// Splits each merge of the given plan whose fan-in exceeds `max_fanin` into a
+/// cascade of merges within the budget, by merging groups of its input
+/// partitions with [`GroupedSortPreservingMergeExec`]s first.
+fn cascade_merges_over_fanin(
+    plan: Arc<dyn ExecutionPlan>,
+    max_fanin: usize,
+) -> Result<Transformed<Arc<dyn ExecutionPlan>>> {
+    plan.transform_up(|node| {
+        if node.as_any().is::<GroupedSortPreservingMergeExec>()
+            || !merge_fanin(&node).is_some_and(|fanin| fanin > max_fanin)
+        {
+            return Ok(Transformed::no(node));
+        }
+        let mut input = Arc::clone(node.children()[0]);
+        let Some(ordering) = input.output_ordering().map(|ordering| ordering.to_vec())
+        else {
+            return Ok(Transformed::no(node));
+        };
+        while input.output_partitioning().partition_count() > max_fanin {
+            input = Arc::new(GroupedSortPreservingMergeExec::new(
+                ordering.clone(),
+                input,
+                max_fanin,
+            ));
+        }
+        node.with_new_children(vec![input]).map(Transformed::yes)
+    })
+}
+
+/// Splits the merges of the `alternate` plan that replaces the given
+/// `sort_plan` whose fan-in exceeds the budget set by the
+/// `optimizer.max_order_preserving_merge_fanin` config option into cascades
+/// (see [`cascade_merges_over_fanin`]) if the sort breaks the pipeline of an
+/// unbounded input. A bounded alternate with such merges is declined instead
+/// (see [`merging_kept_reason`]).
+fn cascade_merges_replacing_sort(
+    sort_plan: &Arc<dyn ExecutionPlan>,
+    alternate: Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let pipeline_friendly = sort_plan.execution_mode().pipeline_friendly();
+    match max_merge_fanin(config).filter(|_| !pipeline_friendly) {
+        Some(max_fanin) => cascade_merges_over_fanin(alternate, max_fanin).data(),
+        None => Ok(alternate),
+    }
+}
+
+/// Checks whether the merges of the `alternate` plan that replaces the given
+/// `sort_plan` and its `original_input` should be declined, as sorting is
+/// preferred or expected to be cheaper (see [`prefers_parallel_sorts`] and
+/// [`is_merging_more_expensive`]), or as the bounded `alternate` introduces
+/// merges over the fan-in budget (see [`introduces_merges_over_fanin`]). If
+/// so, returns the reason to keep the sort.
+fn merging_kept_reason(
+    sort_plan: &Arc<dyn ExecutionPlan>,
+    original_input: &Arc<dyn ExecutionPlan>,
+    alternate: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> Option<SortKeptReason> {
+    let sort = sort_plan.as_any().downcast_ref::<SortExec>()?;
+    if prefers_parallel_sorts(sort_plan, original_input, alternate, config) {
+        Some(SortKeptReason::ParallelismBias)
+    } else if is_merging_more_expensive(sort_plan, alternate, sort) {
+        Some(SortKeptReason::MergingMoreExpensive)
+    } else {
+        introduces_merges_over_fanin(original_input, alternate, config)
+            .filter(|_| sort_plan.execution_mode().pipeline_friendly())
+            .map(|(partitions, cap)| SortKeptReason::BlockedByFaninCap {
+                partitions,
+                cap,
+            })
+    }
+}
+
+/// Checks whether the given `merge`, which merges the sorted partitions of the
+/// `original_input` of the given `sort_plan`, should be declined as a
+/// replacement of the sort. Like the order-preserving variant of a
+/// `CoalescePartitionsExec` (see [`plan_with_order_preserving_variants`]), such
+/// a merge requires the `optimizer.enable_order_preserving_coalesce` config
+/// option, and the `replacement_filter` (if any) to accept the sort. It is also
+/// subject to the checks of [`merging_kept_reason`]. If declined, returns the
+/// reason to keep the sort.
+fn sort_merge_kept_reason(
+    sort_plan: &Arc<dyn ExecutionPlan>,
+    original_input: &Arc<dyn ExecutionPlan>,
+    merge: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+    replacement_filter: Option<&ReplacementFilter>,
+) -> Option<SortKeptReason> {
+    if !config.optimizer.enable_order_preserving_coalesce {
+        Some(SortKeptReason::MergeDisabled)
+    } else if !is_replacement_allowed(replacement_filter, sort_plan) {
+        Some(SortKeptReason::ReplacementRejected)
+    } else {
+        merging_kept_reason(sort_plan, original_input, merge, config)
+    }
+}
+
+/// Checks whether replacing the operators connected to the given sort input
+/// with their order-preserving variants is free; i.e. every `RepartitionExec`
+/// or `CoalescePartitionsExec` to replace has a single input partition, so
+/// that its variant does not need to merge.
+fn has_only_free_conversions(opc: &OrderPreservationContext) -> bool {
+    let plan = &opc.plan;
+    let is_free = if is_repartition(plan) || is_coalesce_partitions(plan) {
+        plan.children()[0].output_partitioning().partition_count() <= 1
+    } else {
+        !is_interleave(plan)
+    };
+    is_free
+        && opc
+            .children
+            .iter()
+            .all(|child| child.data.is_none() || has_only_free_conversions(child))
+}
+
+/// Checks whether any source below the given plan is expensive to re-scan (see
+/// [`OrderPreservingHints::is_rescan_expensive`]).
+fn is_over_expensive_rescan_source(
+    plan: &Arc<dyn ExecutionPlan>,
+    hints: &dyn OrderPreservingHints,
+) -> bool {
+    plan.exists(|node| {
+        Ok(node.children().is_empty() && hints.is_rescan_expensive(node.as_ref()))
+    })
+    .unwrap_or(false)
+}
+
+/// Checks whether the given node is a [`SortExec`] whose input has at most one
+/// row as it is fed by a [`PlaceholderRowExec`] or an [`EmptyExec`]. Such a
+/// sort is a no-op as long as it neither changes the partitioning nor limits
+/// the rows.
+fn is_sort_over_trivially_ordered_input(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    let Some(sort) = plan.as_any().downcast_ref::<SortExec>() else {
+        return false;
+    };
+    let input = sort.input();
+    if !is_over_placeholder_or_empty(input) {
+        return false;
+    }
+    let Ok(Statistics {
+        num_rows: Precision::Exact(num_rows),
+        ..
+    }) = input.statistics()
+    else {
+        return false;
+    };
+    num_rows <= 1
+        && !matches!(sort.fetch(), Some(fetch) if fetch < num_rows)
+        && input.output_partitioning().partition_count()
+            == plan.output_partitioning().partition_count()
+}
+
+/// Checks whether the given node is a [`SortExec`] without a fetch whose input
+/// already satisfies its ordering within the same number of partitions; e.g.
+/// the ordering is established by a window operator below the sort, or a hash
+/// [`RepartitionExec`] routes its single input partition to a single output
+/// partition (e.g. `Hash([c], 1)`) and so passes rows through in their input
+/// order. Such a sort is a no-op (`EnforceSorting` may leave one behind
+/// depending on the order of its sub-rules), so there is no need to convert
+/// any operator below it.
+fn is_sort_over_ordered_input(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    let Some(sort) = plan.as_any().downcast_ref::<SortExec>() else {
+        return false;
+    };
+    let input = sort.input();
+    sort.fetch().is_none()
+        && input.output_partitioning().partition_count()
+            == plan.output_partitioning().partition_count()
+        && input.equivalence_properties().ordering_satisfy(sort.expr())
+}
+
+/// Checks whether the given node is a [`SortExec`] that merges the partitions
+/// of a source (i.e. a leaf node, such as a scan of multiple files) whose
+/// partitions each satisfy the ordering of the sort. If so, and merging is
+/// desirable (see [`replace_with_order_preserving_variants`]), returns a
+/// [`SortPreservingMergeExec`] that merges the sorted partitions instead of
+/// sorting all rows again. Otherwise, returns `None`.
+fn merge_sorted_source_partitions(
+    plan: &Arc<dyn ExecutionPlan>,
+    is_spm_better: bool,
+    config: &ConfigOptions,
+) -> Option<Arc<dyn ExecutionPlan>> {
+    let sort = plan.as_any().downcast_ref::<SortExec>()?;
+    let source = sort.input();
+    let eq_properties = source.equivalence_properties();
+    if sort.preserve_partitioning()
+        || !source.children().is_empty()
+        || source.output_partitioning().partition_count() <= 1
+        || !eq_properties.ordering_satisfy(sort.expr())
+    {
+        return None;
+    }
+    if !(is_spm_better
+        || config.optimizer.prefer_existing_sort
+        || !plan.execution_mode().pipeline_friendly())
+    {
+        return None;
+    }
+    let ordering = minimal_ordering(sort.expr(), eq_properties);
+    let merge = SortPreservingMergeExec::new(ordering, Arc::clone(source))
+        .with_fetch(sort.fetch())
+        .with_prefetch(config.execution.sort_preserving_merge_batch_prefetch)
+        .with_annotation(order_preserving_annotation(config));
+    Some(Arc::new(merge))
+}
+
+/// Checks whether the given sort expressions are the exact reverse of a prefix
+/// of the given ordering; i.e. each expression matches the corresponding one in
+/// the ordering, with both its direction and its null placement flipped.
+fn is_reverse_ordering(
+    sort_exprs: &[PhysicalSortExpr],
+    ordering: &[PhysicalSortExpr],
+) -> bool {
+    !sort_exprs.is_empty()
+        && sort_exprs.len() <= ordering.len()
+        && sort_exprs.iter().zip(ordering).all(|(sort_expr, expr)| {
+            sort_expr.expr.eq(&expr.expr) && sort_expr.options == !expr.options
+        })
+}
+
+/// Checks whether the given node is a [`SortExec`] that sorts on the reverse of
+/// the ordering of its input, where that ordering comes from a node that can
+/// scan its partitions in reverse (see [`OrderPreservingHints::can_reverse_scan`])
+/// below a chain of order-maintaining operators. If so, returns the chain over
+/// the reversed scan, merged by a [`SortPreservingMergeExec`] if the sort merges
+/// multiple partitions or has a fetch. Otherwise, returns `None`.
+fn reverse_scan_below_sort(
+    plan: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+    hints: &dyn OrderPreservingHints,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    let Some(sort) = plan.as_any().downcast_ref::<SortExec>() else {
+        return Ok(None);
+    };
+    let Some(ordering) = sort.input().output_ordering() else {
+        return Ok(None);
+    };
+    if !is_reverse_ordering(sort.expr(), ordering) {
+        return Ok(None);
+    }
+    let mut chain = vec![];
+    let mut node = Arc::clone(sort.input());
+    while !hints.can_reverse_scan(node.as_ref()) {
+        if node.children().len() != 1
+            || node.maintains_input_order() != [true]
+            || !hints.has_deterministic_partitioning(node.as_ref())
+        {
+            return Ok(None);
+        }
+        let child = Arc::clone(node.children()[0]);
+        chain.push(node);
+        node = child;
+    }
+    let Some(mut reversed) = hints.with_reversed_scan(node.as_ref()) else {
+        return Ok(None);
+    };
+    for parent in chain.into_iter().rev() {
+        reversed = parent.with_new_children(vec![reversed])?;
+    }
+    if !reversed
+        .equivalence_properties()
+        .ordering_satisfy(sort.expr())
+    {
+        return Ok(None);
+    }
+    let input_partitions = reversed.output_partitioning().partition_count();
+    let output_partitions = plan.output_partitioning().partition_count();
+    if output_partitions == 1 && (input_partitions > 1 || sort.fetch().is_some()) {
+        let ordering = minimal_ordering(sort.expr(), reversed.equivalence_properties());
+        let spm = SortPreservingMergeExec::new(ordering, reversed)
+            .with_fetch(sort.fetch())
+            .with_prefetch(config.execution.sort_preserving_merge_batch_prefetch)
+            .with_annotation(order_preserving_annotation(config));
+        Ok(Some(Arc::new(spm)))
+    } else if input_partitions == output_partitions && sort.fetch().is_none() {
+        Ok(Some(reversed))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Checks whether the given node is a [`DataSinkExec`] whose input ordering
+/// requirement is fulfilled by a `SortExec` below it. The `SortExec` is either
+/// directly below the sink, or sorts each partition separately below a
+/// [`SortPreservingMergeExec`] that merges the sorted partitions. In both cases,
+/// the sink needs the ordering of the `SortExec` input preserved.
+fn is_sort_required_by_sink(opc: &OrderPreservationContext) -> bool {
+    let Some(sink) = opc.plan.as_any().downcast_ref::<DataSinkExec>() else {
+        return false;
+    };
+    let child = &opc.children[0].plan;
+    let is_sort_below = is_sort(child)
+        || (is_sort_preserving_merge(child) && is_sort(child.children()[0]));
+    is_sort_below
+        && sink.sort_order().as_ref().is_some_and(|sort_order| {
+            child
+                .equivalence_properties()
+                .ordering_satisfy_requirement(sort_order)
+        })
+}
+
+/// Revisits a `SortExec` whose ordering is required by a sink with
+/// order-preserving variants enabled.
+fn revisit_sort_required_by_sink(
+    sort: OrderPreservationContext,
+    config: &ConfigOptions,
+    replacement_filter: Option<&ReplacementFilter>,
+    hints: &dyn OrderPreservingHints,
+    decisions: Option<&mut Vec<SortRemovalDecision>>,
+) -> Result<OrderPreservationContext> {
+    let sort = recompute_ordering_connections(sort.plan, hints);
+    replace_with_order_preserving_variants_and_record(
+        sort,
+        true,
+        true,
+        config,
+        replacement_filter,
+        hints,
+        decisions,
+    )
+    .map(|t| t.data)
+}
+
+/// Revisits a `SortExec` that sorts all partitions of its input into a single
+/// partition to produce the ordering required by a sink; e.g. the sort that
+/// orders the rows inserted into a table clustered on the sort key. Such a
+/// sort can not be replaced by an alternate plan with multiple partitions, so
+/// it is split into a [`SortPreservingMergeExec`] over a sort of each partition,
+/// and the latter is revisited. The split is kept only if the per-partition
+/// sort is removed, otherwise the original sort is returned. Like other merges
+/// replacing a sort, the merge is subject to the fan-in budget set by the
+/// `optimizer.max_order_preserving_merge_fanin` config option.
+fn revisit_merging_sort_required_by_sink(
+    sort: OrderPreservationContext,
+    config: &ConfigOptions,
+    replacement_filter: Option<&ReplacementFilter>,
+    hints: &dyn OrderPreservingHints,
+    mut decisions: Option<&mut Vec<SortRemovalDecision>>,
+) -> Result<OrderPreservationContext> {
+    let Some(sort_exec) = sort.plan.as_any().downcast_ref::<SortExec>() else {
+        return Ok(sort);
+    };
+    let input = sort_exec.input();
+    if sort_exec.preserve_partitioning()
+        || input.output_partitioning().partition_count() <= 1
+    {
+        return revisit_sort_required_by_sink(
+            sort,
+            config,
+            replacement_filter,
+            hints,
+            decisions,
+        );
+    }
+    // A bounded sort is kept rather than replaced by a merge over the budget:
+    let partitions = input.output_partitioning().partition_count();
+    if let Some(cap) = max_merge_fanin(config)
+        .filter(|cap| partitions > *cap && sort.plan.execution_mode().pipeline_friendly())
+    {
+        record_decision(
+            &mut decisions,
+            SortOutcome::Kept(SortKeptReason::BlockedByFaninCap { partitions, cap }),
+        );
+        return Ok(sort);
+    }
+    let partition_sort = SortExec::new(sort_exec.expr().to_vec(), Arc::clone(input))
+        .with_preserve_partitioning(true);
+    let revisited = revisit_sort_required_by_sink(
+        OrderPreservationContext::new_default(Arc::new(partition_sort)),
+        config,
+        replacement_filter,
+        hints,
+        decisions,
+    )?;
+    if is_sort(&revisited.plan) {
+        return Ok(sort);
+    }
+    let merge = SortPreservingMergeExec::new(sort_exec.expr().to_vec(), revisited.plan)
+        .with_fetch(sort_exec.fetch())
+        .with_prefetch(config.execution.sort_preserving_merge_batch_prefetch)
+        .with_annotation(order_preserving_annotation(config));
+    let merge = cascade_merges_replacing_sort(&sort.plan, Arc::new(merge), config)?;
+    Ok(OrderPreservationContext::new_default(merge))
+}
+
+/// Checks whether the `alternate` plan that replaces the given `sort` keeps its
+/// output partitioning, so that the input distribution required by the parent
+/// of the sort remains satisfied. The alternate has to produce the same number
+/// of partitions and, if the sort is hash partitioned, it has to be partitioned
+/// by the same expressions.
+fn keeps_output_partitioning(
+    sort: &Arc<dyn ExecutionPlan>,
+    alternate: &Arc<dyn ExecutionPlan>,
+) -> bool {
+    let partitioning = sort.output_partitioning();
+    let alternate_partitioning = alternate.output_partitioning();
+    alternate_partitioning.partition_count() == partitioning.partition_count()
+        && match partitioning {
+            Partitioning::Hash(exprs, _) => alternate_partitioning.satisfy(
+                &Distribution::HashPartitioned(exprs.clone()),
+                alternate.equivalence_properties(),
+            ),
+            _ => true,
         }
+}
+
+/// Annotation of the operators introduced by this sub-rule, see the
+/// `optimizer.annotate_order_preserving_variants` config option.
+const ORDER_PRESERVING_ANNOTATION: &str = "order-preserving-opt";
+
+/// Returns the annotation of the operators introduced by this sub-rule, if
+/// they are to be annotated according to the given config.
+fn order_preserving_annotation(config: &ConfigOptions) -> Option<String> {
+    config
+        .optimizer
+        .annotate_order_preserving_variants
+        .then(|| ORDER_PRESERVING_ANNOTATION.to_string())
+}
+
+/// Checks whether the given node is a [`UnionExec`] with multiple partitions
+/// whose inputs all share its output ordering. Merging its partitions yields
+/// that common ordering, so a sort that merges them can be replaced by a
+/// [`SortPreservingMergeExec`].
+fn is_homogeneously_ordered_union(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    is_union(plan)
+        && plan.output_partitioning().partition_count() > 1
+        && plan.output_ordering().is_some()
+        && plan
+            .maintains_input_order()
+            .iter()
+            .all(|maintains| *maintains)
+}
+
+/// Predicate that decides whether an operator may be replaced with its
+/// order-preserving variant, see
+/// [`ReplaceWithOrderPreservingVariants::with_replacement_filter`].
+type ReplacementFilter = dyn Fn(&dyn ExecutionPlan) -> bool + Send + Sync;
+
+/// Checks whether the given `replacement_filter` (if any) allows replacing the
+/// given operator with its order-preserving variant.
+fn is_replacement_allowed(
+    replacement_filter: Option<&ReplacementFilter>,
+    plan: &Arc<dyn ExecutionPlan>,
+) -> bool {
+    match replacement_filter {
+        Some(replacement_filter) => replacement_filter(plan.as_ref()),
+        None => true,
     }
-    opc.data = false;
+}
+
+/// Checks whether the given node is a `RepartitionExec` that may be replaced
+/// with its order-preserving variant (see [`plan_with_order_preserving_variants`])
+/// directly above a `CoalescePartitionsExec` through which an ordering
+/// connection carrying a non-empty ordering runs, i.e. whose input is (or can
+/// be made) ordered.
+fn is_repartition_over_coalesce(
+    sort_input: &OrderPreservationContext,
+    config: &ConfigOptions,
+) -> bool {
+    is_repartition(&sort_input.plan)
+        && config.optimizer.enable_order_preserving_repartition
+        && (config.optimizer.allow_order_preserving_round_robin
+            || !is_round_robin_repartition(&sort_input.plan))
+        && is_coalesce_partitions(&sort_input.children[0].plan)
+        && sort_input.children[0]
+            .data
+            .as_ref()
+            .is_some_and(|ordering| !ordering.is_empty())
 }
 
 /// Calculates the updated plan by replacing operators that lose ordering
 /// inside `sort_input` with their order-preserving variants. This will
 /// generate an alternative plan, which will be accepted or rejected later on
-/// depending on whether it helps us remove a `SortExec`.
+/// depending on whether it helps us remove a `SortExec`. In best-effort mode
+/// (see the `optimizer.order_preserving_best_effort` config option), a subtree
+/// that fails to convert is left as it is, and the error is collected into
+/// `conversion_errors`.
 fn plan_with_order_preserving_variants(
     mut sort_input: OrderPreservationContext,
     // Flag indicating that it is desirable to replace `RepartitionExec`s with
@@ -98,46 +940,162 @@ fn plan_with_order_preserving_variants(
     // Flag indicating that it is desirable to replace `CoalescePartitionsExec`s
     // with `SortPreservingMergeExec`s:
     is_spm_better: bool,
+    config: &ConfigOptions,
+    // Predicate that has to accept an operator before it is replaced with its
+    // order-preserving variant, if given:
+    replacement_filter: Option<&ReplacementFilter>,
+    conversion_errors: &mut Vec<String>,
 ) -> Result<OrderPreservationContext> {
+    if is_spr_better && is_repartition_over_coalesce(&sort_input, config) {
+        // A `RepartitionExec` above a `CoalescePartitionsExec` re-partitions
+        // the single partition that the latter collapses its input into. Let
+        // the repartition read the partitions of that input directly, so that
+        // it can be replaced with a single order-preserving variant, which
+        // merges them while re-partitioning:
+        let coalesce = sort_input.children.swap_remove(0);
+        if is_replacement_allowed(replacement_filter, &coalesce.plan)
+            && is_replacement_allowed(replacement_filter, &sort_input.plan)
+        {
+            sort_input.children = coalesce.children;
+            sort_input = sort_input.update_plan_from_children()?;
+        } else {
+            sort_input.children = vec![coalesce];
+        }
+    }
     sort_input.children = sort_input
         .children
         .into_iter()
         .map(|node| {
             // Update descendants in the given tree if there is a connection:
-            if node.data {
-                plan_with_order_preserving_variants(node, is_spr_better, is_spm_better)
+            if node.data.is_some() {
+                let original_plan = Arc::clone(&node.plan);
+                match plan_with_order_preserving_variants(
+                    node,
+                    is_spr_better,
+                    is_spm_better,
+                    config,
+                    replacement_filter,
+                    conversion_errors,
+                ) {
+                    // Leave the failing subtree as is in best-effort mode:
+                    Err(e) if config.optimizer.order_preserving_best_effort => {
+                        debug!(
+                            "Keeping a subtree without order-preserving variants \
+                             as converting it failed: {e}"
+                        );
+                        conversion_errors.push(e.to_string());
+                        Ok(OrderPreservationContext::new_default(original_plan))
+                    }
+                    result => result,
+                }
             } else {
                 Ok(node)
             }
         })
         .collect::<Result<_>>()?;
-    sort_input.data = false;
+    sort_input.data = None;
 
     if is_repartition(&sort_input.plan)
         && !sort_input.plan.maintains_input_order()[0]
         && is_spr_better
+        && config.optimizer.enable_order_preserving_repartition
+        && (config.optimizer.allow_order_preserving_round_robin
+            || !is_round_robin_repartition(&sort_input.plan))
+        && is_replacement_allowed(replacement_filter, &sort_input.plan)
     {
         // When a `RepartitionExec` doesn't preserve ordering, replace it with
-        // a sort-preserving variant if appropriate:
+        // a sort-preserving variant if appropriate. The partitioning is copied
+        // verbatim so that the variant distributes rows exactly the same way,
+        // whatever partitioning scheme the original operator used. Note that
+        // the variant always merges its input streams, even if it hashes on
+        // the sort key: every output partition still receives rows from all
+        // input partitions, so concatenating them would not be ordered. A sort
+        // right below the repartition that is made redundant by its own input
+        // is removed as well, as the order-preserving variant keeps the input
+        // ordering anyway:
+        debug_assert!(
+            !sort_input
+                .plan
+                .as_any()
+                .downcast_ref::<RepartitionExec>()
+                .is_some_and(|repartition| repartition.preserve_order()),
+            "RepartitionExec already preserves order"
+        );
+        let input = sort_input.children.swap_remove(0);
+        sort_input.children = vec![remove_redundant_sort(input)];
         let child = sort_input.children[0].plan.clone();
+        let ordering = child.output_ordering().map(<[_]>::to_vec);
         let partitioning = sort_input.plan.output_partitioning().clone();
         sort_input.plan = Arc::new(
-            RepartitionExec::try_new(child, partitioning)?.with_preserve_order(),
+            RepartitionExec::try_new(child, partitioning)?
+                .with_preserve_order()
+                .with_preserve_order_batch_size(
+                    config.optimizer.preserve_order_repartition_batch_size,
+                )
+                .with_annotation(order_preserving_annotation(config)),
         ) as _;
-        sort_input.children[0].data = true;
+        sort_input.children[0].data = Some(ordering.unwrap_or_default());
         return Ok(sort_input);
-    } else if is_coalesce_partitions(&sort_input.plan) && is_spm_better {
+    } else if is_coalesce_partitions(&sort_input.plan)
+        && is_spm_better
+        && config.optimizer.enable_order_preserving_coalesce
+        && is_replacement_allowed(replacement_filter, &sort_input.plan)
+    {
+        if is_interleave(&sort_input.children[0].plan) {
+            // An `InterleaveExec` loses the orderings of its inputs, but merging
+            // all partitions of all its inputs yields the same rows. Replace it
+            // with a `UnionExec`, which keeps the ordering common to its inputs,
+            // so that the merge below can be introduced:
+            let interleave = &mut sort_input.children[0];
+            let inputs = interleave.plan.children().into_iter().cloned().collect();
+            let union = UnionExec::new(inputs);
+            if union.properties().output_ordering().is_some() {
+                interleave.plan = Arc::new(union);
+            }
+        }
         let child = &sort_input.children[0].plan;
-        if let Some(ordering) = child.output_ordering().map(Vec::from) {
+        if let Some(ordering) = child.output_ordering() {
             // When the input of a `CoalescePartitionsExec` has an ordering,
-            // replace it with a `SortPreservingMergeExec` if appropriate:
-            let spm = SortPreservingMergeExec::new(ordering, child.clone());
+            // replace it with a `SortPreservingMergeExec` if appropriate. Note
+            // that the merge key is the output ordering of the input itself,
+            // so any projection applied by the input (e.g. by a repartitioning
+            // operator that also projects) is already reflected in it. The
+            // output ordering is valid for all partitions of the input; e.g.
+            // for a `UnionExec` whose children are ordered differently, it is
+            // their common ordering (if any):
+            let ordering = minimal_ordering(ordering, child.equivalence_properties());
+            let spm = SortPreservingMergeExec::new(ordering.clone(), child.clone())
+                .with_prefetch(config.execution.sort_preserving_merge_batch_prefetch)
+                .with_annotation(order_preserving_annotation(config));
             sort_input.plan = Arc::new(spm) as _;
-            sort_input.children[0].data = true;
+            sort_input.children[0].data = Some(ordering);
             return Ok(sort_input);
         }
     }
 
+    let coalesce_batches_fetch = sort_input
+        .plan
+        .as_any()
+        .downcast_ref::<CoalesceBatchesExec>()
+        .and_then(|coalesce_batches| coalesce_batches.fetch());
+    if let Some(fetch) = coalesce_batches_fetch {
+        // A `CoalesceBatchesExec` with a fetch stops after its first `fetch`
+        // rows, so a merge right below it (e.g. one that replaced a
+        // `CoalescePartitionsExec`) can stop as early, like a merge that
+        // replaces a sort with a fetch:
+        let child = &mut sort_input.children[0];
+        if is_sort_preserving_merge(&child.plan)
+            && !child
+                .plan
+                .fetch()
+                .is_some_and(|child_fetch| child_fetch <= fetch)
+        {
+            if let Some(merge) = child.plan.with_fetch(Some(fetch)) {
+                child.plan = merge;
+            }
+        }
+    }
+
     sort_input.update_plan_from_children()
 }
 
@@ -170,9 +1128,13 @@ fn plan_with_order_breaking_variants(
         }
     })
     .collect::<Result<_>>()?;
-    sort_input.data = false;
+    sort_input.data = None;
 
-    if is_repartition(plan) && plan.maintains_input_order()[0] {
+    if plan
+        .as_any()
+        .downcast_ref::<RepartitionExec>()
+        .is_some_and(|repartition| repartition.preserve_order())
+    {
         // When a `RepartitionExec` preserves ordering, replace it with a
         // non-sort-preserving variant:
         let child = sort_input.children[0].plan.clone();
@@ -187,7 +1149,7 @@ fn plan_with_order_breaking_variants(
         return sort_input.update_plan_from_children();
     }
 
-    sort_input.children[0].data = false;
+    sort_input.children[0].data = None;
     Ok(sort_input)
 }
 
@@ -220,8 +1182,18 @@ fn plan_with_order_breaking_variants(
 ///    use updated plan. Otherwise, use the original plan.
 /// 5. Continue the bottom-up traversal until another `SortExec` is seen, or the
 ///    traversal is complete.
+///
+/// When a subplan is shared by multiple consumers (i.e. the same operator is a
+/// child of multiple parents), only the copy below the `SortExec` in question
+/// is replaced with order-preserving variants. Other consumers, which may not
+/// care about ordering, keep using the original (faster) subplan.
+///
+/// A `SortExec` that feeds a [`DataSinkExec`] requiring the same ordering
+/// (either directly or through a [`SortPreservingMergeExec`] merging its sorted
+/// partitions) is revisited with order-preserving variants enabled, as the sink
+/// needs the ordering of its input to be preserved.
 pub(crate) fn replace_with_order_preserving_variants(
-    mut requirements: OrderPreservationContext,
+    requirements: OrderPreservationContext,
     // A flag indicating that replacing `RepartitionExec`s with sort-preserving
     // variants is desirable when it helps to remove a `SortExec` from the plan.
     // If this flag is `false`, this replacement should only be made to fix the
@@ -234,411 +1206,6443 @@ pub(crate) fn replace_with_order_preserving_variants(
     is_spm_better: bool,
     config: &ConfigOptions,
 ) -> Result<Transformed<OrderPreservationContext>> {
-    update_children(&mut requirements);
-    if !(is_sort(&requirements.plan) && requirements.children[0].data) {
+    replace_with_order_preserving_variants_and_record(
+        requirements,
+        is_spr_better,
+        is_spm_better,
+        config,
+        None,
+        &NoHints,
+        None,
+    )
+}
+
+/// Same as [`replace_with_order_preserving_variants`], but also records a
+/// [`SortRemovalDecision`] into `decisions` (if given) when the given node is a
+/// [`SortExec`]. The paths of the recorded decisions are relative to the given
+/// node. A sort whose ordering is required by a sink is revisited, so it may be
+/// reported again by the sink above it; the last decision about a sort is the
+/// one that applies.
+fn replace_with_order_preserving_variants_and_record(
+    mut requirements: OrderPreservationContext,
+    is_spr_better: bool,
+    is_spm_better: bool,
+    config: &ConfigOptions,
+    replacement_filter: Option<&ReplacementFilter>,
+    hints: &dyn OrderPreservingHints,
+    mut decisions: Option<&mut Vec<SortRemovalDecision>>,
+) -> Result<Transformed<OrderPreservationContext>> {
+    update_children_with_hints(&mut requirements, hints);
+    if let Some(merge) = fuse_with_child_merge(&requirements.plan) {
+        return Ok(Transformed::yes(OrderPreservationContext::new_default(
+            merge,
+        )));
+    }
+    if config.optimizer.parallelize_sort {
+        if let Some(spm) = parallelize_sort_over_coalesce(&requirements.plan, config) {
+            record_decision(
+                &mut decisions,
+                SortOutcome::Removed(SortRemovalReason::Parallelized),
+            );
+            return Ok(Transformed::yes(OrderPreservationContext::new_default(spm)));
+        }
+    }
+    if is_sort_required_by_sink(&requirements) {
+        // The sink requires the ordering provided by the `SortExec` below it,
+        // so treat its requirement like a sort requirement and revisit that
+        // `SortExec` with order-preserving variants enabled:
+        let first_decision = decisions.as_deref().map_or(0, Vec::len);
+        let mut child = requirements.children.swap_remove(0);
+        let sort_path = if is_sort_preserving_merge(&child.plan) {
+            // The `SortExec` sorts each partition below the merge:
+            let sort = child.children.swap_remove(0);
+            child.children = vec![revisit_sort_required_by_sink(
+                sort,
+                config,
+                replacement_filter,
+                hints,
+                decisions.as_deref_mut(),
+            )?];
+            child = child.update_plan_from_children()?;
+            [0, 0].as_slice()
+        } else {
+            child = revisit_merging_sort_required_by_sink(
+                child,
+                config,
+                replacement_filter,
+                hints,
+                decisions.as_deref_mut(),
+            )?;
+            [0].as_slice()
+        };
+        if let Some(decisions) = decisions {
+            for decision in &mut decisions[first_decision..] {
+                decision.path.splice(0..0, sort_path.iter().copied());
+            }
+        }
+        // Only report a transformation if the revisit changed the plan:
+        let original_plan = Arc::clone(&requirements.plan);
+        requirements.children = vec![child];
+        let requirements = requirements.update_plan_from_children()?;
+        let transformed = !Arc::ptr_eq(&original_plan, &requirements.plan);
+        return Ok(Transformed::new_transformed(requirements, transformed));
+    }
+    if is_sort_over_trivially_ordered_input(&requirements.plan) {
+        // The input of the sort has at most one row, so it is trivially
+        // ordered and there is no need to convert any operator below it:
+        record_decision(
+            &mut decisions,
+            SortOutcome::Removed(SortRemovalReason::TriviallyOrderedInput),
+        );
+        let mut sort_input = requirements.children.swap_remove(0);
+        sort_input.data = None;
+        return Ok(Transformed::yes(sort_input));
+    }
+    if is_sort_over_ordered_input(&requirements.plan) {
+        // The input of the sort already satisfies its ordering, so the sort
+        // can be dropped without converting any operator below it:
+        record_decision(
+            &mut decisions,
+            SortOutcome::Removed(SortRemovalReason::OrderedInput),
+        );
+        let mut sort_input = requirements.children.swap_remove(0);
+        sort_input.data = None;
+        return Ok(Transformed::yes(sort_input));
+    }
+    if let Some(merge) =
+        merge_sorted_source_partitions(&requirements.plan, is_spm_better, config)
+    {
+        // The sort merges the partitions of a source that are sorted already,
+        // so merging them while preserving their ordering suffices, unless the
+        // merge is declined like any other merge replacing a sort:
+        let source = Arc::clone(requirements.plan.children()[0]);
+        if let Some(kept_reason) = sort_merge_kept_reason(
+            &requirements.plan,
+            &source,
+            &merge,
+            config,
+            replacement_filter,
+        ) {
+            record_decision(&mut decisions, SortOutcome::Kept(kept_reason));
+            return Ok(Transformed::no(requirements));
+        }
+        record_decision(
+            &mut decisions,
+            SortOutcome::Removed(SortRemovalReason::MergedSortedPartitions),
+        );
+        let merge = cascade_merges_replacing_sort(&requirements.plan, merge, config)?;
+        return Ok(Transformed::yes(OrderPreservationContext::new_default(
+            merge,
+        )));
+    }
+    if let Some(reversed) = reverse_scan_below_sort(&requirements.plan, config, hints)? {
+        // The input of the sort can produce the required ordering by scanning
+        // its source backwards, which is cheaper than re-sorting it. Still, a
+        // merge of the reversed partitions replaces the sort like any other
+        // merge, so it is subject to the replacement filter and the fan-in
+        // budget:
+        if is_sort_preserving_merge(&reversed)
+            && !is_replacement_allowed(replacement_filter, &requirements.plan)
+        {
+            record_decision(
+                &mut decisions,
+                SortOutcome::Kept(SortKeptReason::ReplacementRejected),
+            );
+            return Ok(Transformed::no(requirements));
+        }
+        record_decision(
+            &mut decisions,
+            SortOutcome::Removed(SortRemovalReason::ReversedScan),
+        );
+        let reversed = match max_merge_fanin(config) {
+            Some(max_fanin) => cascade_merges_over_fanin(reversed, max_fanin).data()?,
+            None => reversed,
+        };
+        return Ok(Transformed::yes(OrderPreservationContext::new_default(
+            reversed,
+        )));
+    }
+    let Some(sort) = requirements.plan.as_any().downcast_ref::<SortExec>() else {
+        return Ok(Transformed::no(requirements));
+    };
+    if requirements.children[0].data.is_none() {
+        record_decision(
+            &mut decisions,
+            SortOutcome::Kept(SortKeptReason::NoOrderingConnection),
+        );
+        return Ok(Transformed::no(requirements));
+    }
+    // A sort on a volatile expression can not be replaced by preserving the
+    // ordering of its input:
+    if sort
+        .expr()
+        .iter()
+        .any(|sort_expr| is_volatile(&sort_expr.expr))
+    {
+        record_decision(
+            &mut decisions,
+            SortOutcome::Kept(SortKeptReason::VolatileSortKey),
+        );
         return Ok(Transformed::no(requirements));
     }
 
     // For unbounded cases, we replace with the order-preserving variant in any
-    // case, as doing so helps fix the pipeline. Also replace if config allows.
+    // case, as doing so helps fix the pipeline. Also replace if config allows
+    // (possibly only if the conversion is free), if the sort is expected to
+    // spill (merging streams without disk I/O), or if the sort could spill and
+    // re-read data from a source that is expensive to re-scan.
+    let pipeline_friendly = requirements.plan.execution_mode().pipeline_friendly();
     let use_order_preserving_variant = config.optimizer.prefer_existing_sort
-        || !requirements.plan.execution_mode().pipeline_friendly();
+        || !pipeline_friendly
+        || (config.optimizer.prefer_existing_sort_when_free
+            && has_only_free_conversions(&requirements.children[0]))
+        || is_sort_expected_to_spill(&requirements.children[0].plan, config)
+        || is_over_expensive_rescan_source(&requirements.children[0].plan, hints);
 
     // Create an alternate plan with order-preserving variants:
-    let mut alternate_plan = plan_with_order_preserving_variants(
-        requirements.children.swap_remove(0),
+    let sort_input = requirements.children.swap_remove(0);
+    let original_plan = Arc::clone(&sort_input.plan);
+    let mut conversion_errors = vec![];
+    let mut alternate_plan = match plan_with_order_preserving_variants(
+        sort_input,
         is_spr_better || use_order_preserving_variant,
         is_spm_better || use_order_preserving_variant,
-    )?;
+        config,
+        replacement_filter,
+        &mut conversion_errors,
+    ) {
+        Ok(alternate_plan) => alternate_plan,
+        Err(e) if config.optimizer.order_preserving_best_effort => {
+            // Keep the sort and its original input in best-effort mode:
+            debug!(
+                "Keeping a sort without order-preserving variants below it as \
+                 converting its input failed: {e}"
+            );
+            record_decision(
+                &mut decisions,
+                SortOutcome::Kept(SortKeptReason::ConversionFailed {
+                    error: e.to_string(),
+                }),
+            );
+            requirements.children =
+                vec![OrderPreservationContext::new_default(original_plan)];
+            return Ok(Transformed::no(requirements));
+        }
+        Err(e) => return Err(e),
+    };
+    let mut union_merge_kept_reason = None;
+    if !sort.preserve_partitioning()
+        && (is_spm_better || use_order_preserving_variant)
+        && is_homogeneously_ordered_union(&alternate_plan.plan)
+    {
+        // The sort merges the partitions of a union whose inputs share an
+        // ordering, so merge them while preserving that ordering instead,
+        // unless the merge is declined like any other merge replacing a sort:
+        if !config.optimizer.enable_order_preserving_coalesce {
+            union_merge_kept_reason = Some(SortKeptReason::MergeDisabled);
+        } else if !is_replacement_allowed(replacement_filter, &requirements.plan) {
+            union_merge_kept_reason = Some(SortKeptReason::ReplacementRejected);
+        } else {
+            let ordering = minimal_ordering(
+                sort.expr(),
+                alternate_plan.plan.equivalence_properties(),
+            );
+            let merge = SortPreservingMergeExec::new(ordering, alternate_plan.plan)
+                .with_prefetch(config.execution.sort_preserving_merge_batch_prefetch)
+                .with_annotation(order_preserving_annotation(config));
+            alternate_plan = OrderPreservationContext::new_default(Arc::new(merge));
+        }
+    }
 
-    // If the alternate plan makes this sort unnecessary, accept the alternate:
-    if alternate_plan
+    // If the alternate plan makes this sort unnecessary, accept the alternate.
+    // The requirement to satisfy is the sort expressions of the sort, not its
+    // output ordering (which may be affected by its input's equivalences).
+    // Note that a sort that does not preserve partitioning also merges its
+    // input partitions, and operators above it may rely on this. Therefore,
+    // the alternate is only accepted if it keeps the output partitioning (see
+    // `keeps_output_partitioning`).
+    // In particular, a sort at the root of the plan that produces the single
+    // result stream is never replaced by a multi-partition alternate.
+    // A sort with a fetch (e.g. a limit with an offset fused into it, in which
+    // case the fetch already covers the skipped rows) can only be replaced if
+    // the alternate ends with a merge that can apply the same fetch globally.
+    // In bounded plans, the alternate is also rejected if the statistics show
+    // that its merges are more expensive than the sort, or if it introduces
+    // merges over the fan-in budget. In unbounded plans, such merges are split
+    // into cascades of merges within the budget instead:
+    let over_fanin_budget =
+        introduces_merges_over_fanin(&original_plan, &alternate_plan.plan, config)
+            .filter(|_| pipeline_friendly);
+    let fetch = sort.fetch();
+    let kept_reason = if !alternate_plan
         .plan
         .equivalence_properties()
-        .ordering_satisfy(requirements.plan.output_ordering().unwrap_or(&[]))
+        .ordering_satisfy(sort.expr())
+    {
+        Some(SortKeptReason::OrderingNotSatisfied)
+    } else if union_merge_kept_reason.is_some() {
+        union_merge_kept_reason
+    } else if !keeps_output_partitioning(&requirements.plan, &alternate_plan.plan) {
+        Some(SortKeptReason::PartitioningChanged)
+    } else if fetch.is_some() && !is_sort_preserving_merge(&alternate_plan.plan) {
+        Some(SortKeptReason::UnmergeableFetch)
+    } else {
+        merging_kept_reason(
+            &requirements.plan,
+            &original_plan,
+            &alternate_plan.plan,
+            config,
+        )
+    };
+    // A conversion that failed in best-effort mode is the root cause of the
+    // other reasons to keep the sort:
+    let kept_reason =
+        kept_reason.map(|reason| match conversion_errors.into_iter().next() {
+            Some(error) => SortKeptReason::ConversionFailed { error },
+            None => reason,
+        });
+    let Some(kept_reason) = kept_reason else {
+        record_decision(
+            &mut decisions,
+            SortOutcome::Removed(SortRemovalReason::OrderPreservingVariants),
+        );
+        if let Some(merge) = fetch.and_then(|_| alternate_plan.plan.with_fetch(fetch)) {
+            alternate_plan.plan = merge;
+        }
+        // The alternate may end with a merge introduced above an existing one,
+        // which would merge the same (single) sorted stream again:
+        if let Some(merge) = fuse_with_child_merge(&alternate_plan.plan) {
+            alternate_plan = OrderPreservationContext::new_default(merge);
+        }
+        if config
+            .optimizer
+            .remove_round_robin_below_ordered_repartition
+        {
+            let simplified =
+                remove_single_input_round_robins(Arc::clone(&alternate_plan.plan))?;
+            if simplified.transformed {
+                alternate_plan = OrderPreservationContext::new_default(simplified.data);
+            }
+        }
+        let cascaded = cascade_merges_replacing_sort(
+            &requirements.plan,
+            Arc::clone(&alternate_plan.plan),
+            config,
+        )?;
+        if !Arc::ptr_eq(&cascaded, &alternate_plan.plan) {
+            alternate_plan = OrderPreservationContext::new_default(cascaded);
+        }
+        if config.optimizer.emit_distinct_preserve_order_type {
+            let plan = with_distinct_preserve_order_type(alternate_plan.plan)?;
+            return Ok(Transformed::yes(OrderPreservationContext::new_default(
+                plan,
+            )));
+        }
+        for child in alternate_plan.children.iter_mut() {
+            child.data = None;
+        }
+        return Ok(Transformed::yes(alternate_plan));
+    };
+    record_decision(&mut decisions, SortOutcome::Kept(kept_reason));
+    if let Some(prefix_length) = partial_sort_prefix_length(
+        &requirements.plan,
+        &original_plan,
+        &alternate_plan.plan,
+        config,
+    )
+    .filter(|_| over_fanin_budget.is_none())
     {
+        // The alternate plan satisfies a longer prefix of the ordering than the
+        // original input, so only the rows with equal prefix values need to be
+        // sorted:
+        let partial_sort = PartialSortExec::new(
+            sort.expr().to_vec(),
+            Arc::clone(&alternate_plan.plan),
+            prefix_length,
+        )
+        .with_preserve_partitioning(sort.preserve_partitioning())
+        .with_fetch(fetch);
         for child in alternate_plan.children.iter_mut() {
-            child.data = false;
+            child.data = None;
         }
-        Ok(Transformed::yes(alternate_plan))
-    } else {
-        // The alternate plan does not help, use faster order-breaking variants:
-        alternate_plan = plan_with_order_breaking_variants(alternate_plan)?;
-        alternate_plan.data = false;
+        alternate_plan.data = None;
+        requirements.plan = Arc::new(partial_sort);
         requirements.children = vec![alternate_plan];
         Ok(Transformed::yes(requirements))
+    } else {
+        // The alternate plan does not help, so use faster order-breaking
+        // variants in the original input of the sort instead:
+        let mut sort_input = plan_with_order_breaking_variants(
+            OrderPreservationContext::new_default(Arc::clone(&original_plan)),
+        )?;
+        sort_input.data = None;
+        // Nothing is rebuilt if nothing has changed below the sort, so that
+        // running the rule again is a no-op:
+        let transformed = !Arc::ptr_eq(&sort_input.plan, &original_plan);
+        requirements.children = vec![sort_input];
+        Ok(Transformed::new_transformed(requirements, transformed))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Records a [`SortRemovalDecision`] about the node being visited into the given
+/// `decisions`, if any.
+fn record_decision(
+    decisions: &mut Option<&mut Vec<SortRemovalDecision>>,
+    outcome: SortOutcome,
+) {
+    if let Some(decisions) = decisions {
+        decisions.push(SortRemovalDecision {
+            path: vec![],
+            outcome,
+        });
+    }
+}
 
-    use crate::datasource::file_format::file_compression_type::FileCompressionType;
-    use crate::datasource::listing::PartitionedFile;
-    use crate::datasource::physical_plan::{CsvExec, FileScanConfig};
-    use crate::physical_optimizer::test_utils::check_integrity;
-    use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
-    use crate::physical_plan::filter::FilterExec;
-    use crate::physical_plan::joins::{HashJoinExec, PartitionMode};
-    use crate::physical_plan::sorts::sort::SortExec;
-    use crate::physical_plan::{
-        displayable, get_plan_string, ExecutionPlan, Partitioning,
+/// Removes the round-robin `RepartitionExec`s with a single input partition
+/// that feed order-preserving hash `RepartitionExec`s in the given plan. Such a
+/// round-robin only spreads the input over more partitions, which the hash
+/// repartition has to merge again to keep the ordering. Reading the single
+/// input partition directly yields the same output partitioning and ordering
+/// without merging.
+fn remove_single_input_round_robins(
+    plan: Arc<dyn ExecutionPlan>,
+) -> Result<Transformed<Arc<dyn ExecutionPlan>>> {
+    plan.transform_up(|node| {
+        let Some(repartition) = node.as_any().downcast_ref::<RepartitionExec>() else {
+            return Ok(Transformed::no(node));
+        };
+        let round_robin = repartition.input();
+        if !repartition.preserve_order()
+            || !matches!(repartition.partitioning(), Partitioning::Hash(..))
+            || !is_round_robin_repartition(round_robin)
+            || round_robin.maintains_input_order() != [true]
+            || round_robin.children()[0]
+                .output_partitioning()
+                .partition_count()
+                != 1
+        {
+            return Ok(Transformed::no(node));
+        }
+        let input = Arc::clone(round_robin.children()[0]);
+        let partitioning = repartition.partitioning().clone();
+        let simplified = RepartitionExec::try_new(input, partitioning)?
+            .with_preserve_order()
+            .with_preserve_order_batch_size(repartition.preserve_order_batch_size())
+            .with_annotation(repartition.annotation().map(String::from));
+        Ok(Transformed::yes(Arc::new(simplified) as _))
+    })
+}
+
+/// Returns the length of the longest prefix of the given sort expressions that
+/// the output of the given plan satisfies.
+fn satisfied_prefix_length(
+    plan: &Arc<dyn ExecutionPlan>,
+    sort_exprs: &[PhysicalSortExpr],
+) -> usize {
+    let eq_properties = plan.equivalence_properties();
+    (1..=sort_exprs.len())
+        .take_while(|len| eq_properties.ordering_satisfy(&sort_exprs[..*len]))
+        .last()
+        .unwrap_or(0)
+}
+
+/// Checks whether the given [`SortExec`] can be replaced by a [`PartialSortExec`]
+/// over the `alternate` plan that replaces its `original_input`, because the
+/// `alternate` satisfies a longer (but not complete) prefix of the ordering of
+/// the sort. If so, returns the length of the satisfied prefix.
+fn partial_sort_prefix_length(
+    sort_plan: &Arc<dyn ExecutionPlan>,
+    original_input: &Arc<dyn ExecutionPlan>,
+    alternate: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> Option<usize> {
+    let sort = sort_plan.as_any().downcast_ref::<SortExec>()?;
+    let prefix_length = satisfied_prefix_length(alternate, sort.expr());
+    if prefix_length == 0
+        || prefix_length == sort.expr().len()
+        || prefix_length <= satisfied_prefix_length(original_input, sort.expr())
+    {
+        return None;
+    }
+    // A `PartialSortExec` sorts each partition on its own, so it can only
+    // replace a sort that merges its input partitions if there is a single one:
+    let partition_count = alternate.output_partitioning().partition_count();
+    let keeps_partitioning = if sort.preserve_partitioning() {
+        partition_count == original_input.output_partitioning().partition_count()
+    } else {
+        partition_count == 1
     };
-    use crate::prelude::SessionConfig;
-    use crate::test::TestStreamPartition;
+    (keeps_partitioning
+        && !prefers_parallel_sorts(sort_plan, original_input, alternate, config)
+        && !is_merging_more_expensive(sort_plan, alternate, sort))
+    .then_some(prefix_length)
+}
 
-    use arrow::compute::SortOptions;
-    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
-    use datafusion_common::tree_node::{TransformedResult, TreeNode};
-    use datafusion_common::Result;
-    use datafusion_execution::object_store::ObjectStoreUrl;
-    use datafusion_expr::{JoinType, Operator};
-    use datafusion_physical_expr::expressions::{self, col, Column};
-    use datafusion_physical_expr::PhysicalSortExpr;
-    use datafusion_physical_plan::streaming::StreamingTableExec;
+/// Reports the order-preserving [`RepartitionExec`]s in the given plan under
+/// the name `SortPreservingRepartitionExec`, see the config option
+/// `optimizer.emit_distinct_preserve_order_type`.
+fn with_distinct_preserve_order_type(
+    plan: Arc<dyn ExecutionPlan>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    plan.transform_up(|node| {
+        let Some(repartition) = node
+            .as_any()
+            .downcast_ref::<RepartitionExec>()
+            .filter(|repartition| repartition.preserve_order())
+        else {
+            return Ok(Transformed::no(node));
+        };
+        let repartition = RepartitionExec::try_new(
+            Arc::clone(repartition.input()),
+            repartition.partitioning().clone(),
+        )?
+        .with_preserve_order()
+        .with_preserve_order_batch_size(repartition.preserve_order_batch_size())
+        .with_annotation(repartition.annotation().map(String::from))
+        .with_sort_preserving_name(true);
+        Ok(Transformed::yes(Arc::new(repartition) as _))
+    })
+    .data()
+}
 
-    use rstest::rstest;
+/// Physical optimizer rule that applies the [`replace_with_order_preserving_variants`]
+/// sub-rule on its own. This allows external physical planners to apply just
+/// this transformation to a plan, without running the whole [`EnforceSorting`]
+/// rule.
+///
+/// # Example
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use arrow::compute::SortOptions;
+/// # use arrow::datatypes::{DataType, Field, Schema};
+/// # use datafusion::config::ConfigOptions;
+/// # use datafusion::error::Result;
+/// # use datafusion::physical_expr::expressions::col;
+/// # use datafusion::physical_expr::PhysicalSortExpr;
+/// # use datafusion::physical_optimizer::replace_with_order_preserving_variants::ReplaceWithOrderPreservingVariants;
+/// # use datafusion::physical_optimizer::PhysicalOptimizerRule;
+/// # use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+/// # use datafusion::physical_plan::memory::MemoryExec;
+/// # use datafusion::physical_plan::sorts::sort::SortExec;
+/// # use datafusion::physical_plan::{displayable, ExecutionPlan};
+/// # fn main() -> Result<()> {
+/// let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+/// let sort_exprs = vec![PhysicalSortExpr {
+///     expr: col("a", &schema)?,
+///     options: SortOptions::default(),
+/// }];
+/// // Two partitions, each sorted on `a`, that are coalesced and sorted again:
+/// let source = MemoryExec::try_new(&[vec![], vec![]], Arc::clone(&schema), None)?
+///     .with_sort_information(vec![sort_exprs.clone()]);
+/// let coalesce = Arc::new(CoalescePartitionsExec::new(Arc::new(source)));
+/// let plan: Arc<dyn ExecutionPlan> = Arc::new(SortExec::new(sort_exprs, coalesce));
+///
+/// // Merging the sorted partitions makes the sort unnecessary:
+/// let rule = ReplaceWithOrderPreservingVariants::new(false, true);
+/// let optimized = rule.optimize(plan, &ConfigOptions::new())?;
+/// assert_eq!(
+///     displayable(optimized.as_ref()).indent(true).to_string(),
+///     "SortPreservingMergeExec: [a@0 ASC]\n  \
+///      MemoryExec: partitions=2, partition_sizes=[0, 0], output_ordering=a@0 ASC\n",
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`EnforceSorting`]: crate::physical_optimizer::enforce_sorting::EnforceSorting
+#[derive(Default, Clone)]
+pub struct ReplaceWithOrderPreservingVariants {
+    is_spr_better: bool,
+    is_spm_better: bool,
+    prefer_existing_sort: Option<bool>,
+    replacement_filter: Option<Arc<ReplacementFilter>>,
+    hints: Option<Arc<dyn OrderPreservingHints>>,
+}
 
-    /// Runs the `replace_with_order_preserving_variants` sub-rule and asserts
-    /// the plan against the original and expected plans for both bounded and
-    /// unbounded cases.
-    ///
-    /// # Parameters
-    ///
-    /// * `EXPECTED_UNBOUNDED_PLAN_LINES`: Expected input unbounded plan.
-    /// * `EXPECTED_BOUNDED_PLAN_LINES`: Expected input bounded plan.
-    /// * `EXPECTED_UNBOUNDED_OPTIMIZED_PLAN_LINES`: Optimized plan, which is
-    ///   the same regardless of the value of the `prefer_existing_sort` flag.
-    /// * `EXPECTED_BOUNDED_OPTIMIZED_PLAN_LINES`: Optimized plan when the flag
-    ///   `prefer_existing_sort` is `false` for bounded cases.
-    /// * `EXPECTED_BOUNDED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES`: Optimized plan
-    ///   when the flag `prefer_existing_sort` is `true` for bounded cases.
-    /// * `$PLAN`: The plan to optimize.
-    /// * `$SOURCE_UNBOUNDED`: Whether the given plan contains an unbounded source.
-    macro_rules! assert_optimized_in_all_boundedness_situations {
-        ($EXPECTED_UNBOUNDED_PLAN_LINES: expr,  $EXPECTED_BOUNDED_PLAN_LINES: expr, $EXPECTED_UNBOUNDED_OPTIMIZED_PLAN_LINES: expr, $EXPECTED_BOUNDED_OPTIMIZED_PLAN_LINES: expr, $EXPECTED_BOUNDED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES: expr, $PLAN: expr, $SOURCE_UNBOUNDED: expr) => {
-            if $SOURCE_UNBOUNDED {
-                assert_optimized_prefer_sort_on_off!(
-                    $EXPECTED_UNBOUNDED_PLAN_LINES,
-                    $EXPECTED_UNBOUNDED_OPTIMIZED_PLAN_LINES,
-                    $EXPECTED_UNBOUNDED_OPTIMIZED_PLAN_LINES,
-                    $PLAN
-                );
-            } else {
-                assert_optimized_prefer_sort_on_off!(
-                    $EXPECTED_BOUNDED_PLAN_LINES,
-                    $EXPECTED_BOUNDED_OPTIMIZED_PLAN_LINES,
-                    $EXPECTED_BOUNDED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES,
-                    $PLAN
-                );
+impl fmt::Debug for ReplaceWithOrderPreservingVariants {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplaceWithOrderPreservingVariants")
+            .field("is_spr_better", &self.is_spr_better)
+            .field("is_spm_better", &self.is_spm_better)
+            .field("prefer_existing_sort", &self.prefer_existing_sort)
+            .field("replacement_filter", &self.replacement_filter.is_some())
+            .field("hints", &self.hints)
+            .finish()
+    }
+}
+
+impl ReplaceWithOrderPreservingVariants {
+    /// Creates a new rule. If `is_spr_better` (`is_spm_better`) is `true`,
+    /// `RepartitionExec`s (`CoalescePartitionsExec`s) are replaced by their
+    /// order-preserving variants whenever this helps to remove a `SortExec`.
+    /// Otherwise, they are only replaced to fix the pipeline of unbounded
+    /// plans, or if the `prefer_existing_sort` config option is set.
+    pub fn new(is_spr_better: bool, is_spm_better: bool) -> Self {
+        Self {
+            is_spr_better,
+            is_spm_better,
+            prefer_existing_sort: None,
+            replacement_filter: None,
+            hints: None,
+        }
+    }
+
+    /// Sets a predicate that is consulted before an operator (a
+    /// `RepartitionExec` or a `CoalescePartitionsExec`) is replaced with its
+    /// order-preserving variant, or before a `SortExec` is replaced with a
+    /// merge of its sorted input partitions. If the predicate returns `false`,
+    /// the original operator is kept; e.g. when merging is known to be
+    /// expensive on the hardware that executes the plan.
+    pub fn with_replacement_filter(
+        mut self,
+        replacement_filter: impl Fn(&dyn ExecutionPlan) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.replacement_filter = Some(Arc::new(replacement_filter));
+        self
+    }
+
+    /// Sets the [`OrderPreservingHints`] that describe the operators of the
+    /// optimized plans to this rule; e.g. the custom operators of a system
+    /// built on DataFusion.
+    pub fn with_hints(mut self, hints: Arc<dyn OrderPreservingHints>) -> Self {
+        self.hints = Some(hints);
+        self
+    }
+
+    /// Returns the hints of this rule, or the defaults if no hints are set.
+    fn hints(&self) -> &dyn OrderPreservingHints {
+        self.hints.as_deref().unwrap_or(&NoHints)
+    }
+
+    /// Overrides the `prefer_existing_sort` config option for the invocations
+    /// of this rule, if `prefer_existing_sort` is `Some`. This allows to
+    /// control the option per query without changing the session config.
+    pub fn with_prefer_existing_sort(
+        mut self,
+        prefer_existing_sort: Option<bool>,
+    ) -> Self {
+        self.prefer_existing_sort = prefer_existing_sort;
+        self
+    }
+
+    /// Returns the given config with the overrides of this rule applied.
+    fn effective_config<'a>(&self, config: &'a ConfigOptions) -> Cow<'a, ConfigOptions> {
+        match self.prefer_existing_sort {
+            Some(prefer_existing_sort)
+                if prefer_existing_sort != config.optimizer.prefer_existing_sort =>
+            {
+                let mut config = config.clone();
+                config.optimizer.prefer_existing_sort = prefer_existing_sort;
+                Cow::Owned(config)
             }
-        };
+            _ => Cow::Borrowed(config),
+        }
     }
 
-    /// Runs the `replace_with_order_preserving_variants` sub-rule and asserts
-    /// the plan against the original and expected plans.
-    ///
-    /// # Parameters
+    /// Optimizes the given plan like [`PhysicalOptimizerRule::optimize`], and
+    /// returns the optimized plan along with a [`SortRemovalDecision`] for each
+    /// [`SortExec`] visited by the rule, in the order of the visits. This helps
+    /// to find out why a sort is kept in the plan.
+    pub fn optimize_and_record_decisions(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<(Arc<dyn ExecutionPlan>, Vec<SortRemovalDecision>)> {
+        let mut decisions = vec![];
+        let optimized = self.optimize_with_observer(plan, config, &mut decisions)?;
+        Ok((optimized, decisions))
+    }
+
+    /// Optimizes the given plan like [`PhysicalOptimizerRule::optimize`], and
+    /// returns the optimized plan along with the identifiers of the removed
+    /// sorts, in the order of their removal. The identifiers are derived from
+    /// the decisions recorded by
+    /// [`Self::optimize_and_record_decisions`], so each one carries the
+    /// reason reported where the sort was removed.
+    pub fn optimize_and_collect_removed_sorts(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<(Arc<dyn ExecutionPlan>, Vec<RemovedSortId>)> {
+        let (optimized, decisions) = self.optimize_and_record_decisions(plan, config)?;
+        let mut removed_sorts: Vec<RemovedSortId> = vec![];
+        for decision in decisions {
+            // A later decision about the same sort overrides the earlier one:
+            removed_sorts.retain(|removed_sort| removed_sort.path != decision.path);
+            if let SortOutcome::Removed(reason) = decision.outcome {
+                removed_sorts.push(RemovedSortId {
+                    path: decision.path,
+                    reason,
+                });
+            }
+        }
+        Ok((optimized, removed_sorts))
+    }
+
+    /// Optimizes the given plan like [`PhysicalOptimizerRule::optimize`], and
+    /// reports the progress of the optimization to the given `observer`.
+    pub fn optimize_with_observer(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+        observer: &mut dyn OrderPreservingObserver,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.optimize_observed(plan, config, Some(observer))
+    }
+
+    /// Optimizes the given plan like [`PhysicalOptimizerRule::optimize`] until
+    /// the given `deadline` passes. Once it passes, the remaining nodes are left
+    /// as they are and the partially optimized plan is returned. As the
+    /// sub-rule rewrites every subtree into a valid plan, the partially
+    /// optimized plan is always valid as well.
+    pub fn optimize_with_deadline(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+        deadline: Instant,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.optimize_observed(plan, config, Some(&mut Deadline(deadline)))
+    }
+
+    /// Optimizes the given plan like [`PhysicalOptimizerRule::optimize`], and
+    /// then replaces every [`SortExec`] that survives the optimization with the
+    /// plan returned by `sort_factory`. This lets embedders plug in their own
+    /// sort implementation for the sorts that can not be removed. Using a
+    /// factory that rebuilds the given [`SortExec`] as is, is equivalent to
+    /// running the rule alone.
+    pub fn optimize_with_sort_factory(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+        sort_factory: &dyn Fn(&SortExec) -> Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.optimize(plan, config)?
+            .transform_up(|plan| {
+                Ok(match plan.as_any().downcast_ref::<SortExec>() {
+                    Some(sort) => Transformed::yes(sort_factory(sort)),
+                    None => Transformed::no(plan),
+                })
+            })
+            .data()
+    }
+
+    /// Optimizes the given plan like [`PhysicalOptimizerRule::optimize`], and
+    /// returns the optimized plan along with the number of sorts in the plan
+    /// before and after the optimization. Sorts fused into other operators
+    /// (i.e. [`PartialSortExec`]s) are also counted.
+    pub fn optimize_and_count_sorts(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<(Arc<dyn ExecutionPlan>, usize, usize)> {
+        let before = count_sorts(&plan)?;
+        let optimized = self.optimize(plan, config)?;
+        let after = count_sorts(&optimized)?;
+        Ok((optimized, before, after))
+    }
+
+    /// Optimizes the given plan like [`PhysicalOptimizerRule::optimize`], and
+    /// returns the optimized plan along with the number of sorts per
+    /// [`SortOrigin`] in the plan before and after the optimization. This
+    /// allows reporting the removal of sorts that implement a user-written
+    /// `ORDER BY` separately from the removal of sorts required by operators.
+    pub fn optimize_and_count_sorts_by_origin(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<(Arc<dyn ExecutionPlan>, SortOriginCounts, SortOriginCounts)> {
+        let before = count_sorts_by_origin(&plan);
+        let optimized = self.optimize(plan, config)?;
+        let after = count_sorts_by_origin(&optimized);
+        Ok((optimized, before, after))
+    }
+
+    /// Computes the order-preserving alternative that this rule builds for the
+    /// input of the given [`SortExec`], without deciding whether it replaces
+    /// the sort and without changing the given plan. Conversions are not
+    /// restricted by the `optimizer.prefer_existing_sort` flag, which only
+    /// plays a role when the alternative is accepted. This allows tools (e.g.
+    /// for `EXPLAIN`) to show the alternative side by side with the original
+    /// plan.
     ///
-    /// * `$EXPECTED_PLAN_LINES`: Expected input plan.
-    /// * `EXPECTED_OPTIMIZED_PLAN_LINES`: Optimized plan when the flag
-    ///   `prefer_existing_sort` is `false`.
-    /// * `EXPECTED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES`: Optimized plan when
-    ///   the flag `prefer_existing_sort` is `true`.
-    /// * `$PLAN`: The plan to optimize.
-    macro_rules! assert_optimized_prefer_sort_on_off {
-        ($EXPECTED_PLAN_LINES: expr, $EXPECTED_OPTIMIZED_PLAN_LINES: expr, $EXPECTED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES: expr, $PLAN: expr) => {
-            assert_optimized!(
-                $EXPECTED_PLAN_LINES,
-                $EXPECTED_OPTIMIZED_PLAN_LINES,
-                $PLAN.clone(),
-                false
-            );
-            assert_optimized!(
-                $EXPECTED_PLAN_LINES,
-                $EXPECTED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES,
-                $PLAN,
-                true
+    /// Returns `None` if the given plan is not a [`SortExec`], or if no
+    /// operator below it could be replaced by an order-preserving variant.
+    pub fn compute_order_preserving_alternative(
+        &self,
+        plan: &Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        if !is_sort(plan) {
+            return Ok(None);
+        }
+        let config = self.effective_config(config);
+        let mut opc = OrderPreservationContext::new_default(Arc::clone(plan))
+            .transform_up(|mut opc| {
+                update_children_with_hints(&mut opc, self.hints());
+                Ok(Transformed::no(opc))
+            })
+            .data()?;
+        let sort_input = opc.children.swap_remove(0);
+        if sort_input.data.is_none() {
+            return Ok(None);
+        }
+        plan_with_order_preserving_variants(
+            sort_input,
+            true,
+            true,
+            &config,
+            self.replacement_filter.as_deref(),
+            &mut vec![],
+        )
+        .map(|alternative| Some(alternative.plan))
+    }
+
+    /// Analyzes the given plan with this rule without applying it, and reports
+    /// a [`Lint`] for every `SortExec` that is removed with the given
+    /// configuration, or that could be removed with a different configuration.
+    pub fn lint_order_preserving(
+        &self,
+        plan: &Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Vec<Lint>> {
+        let config = self.effective_config(config);
+        let permissive_rule = self.clone().with_prefer_existing_sort(Some(true));
+
+        let removes_sort = |rule: &Self, sort: &Arc<dyn ExecutionPlan>| {
+            rule.optimize(Arc::clone(sort), &config)
+                .map(|optimized| !is_sort(&optimized))
+        };
+
+        let mut lints = vec![];
+        plan.apply(|node| {
+            if !is_sort(node) {
+                return Ok(TreeNodeRecursion::Continue);
+            }
+            let sort = displayable(node.as_ref()).one_line().to_string();
+            if removes_sort(self, node)? {
+                lints.push(Lint {
+                    sort: sort.trim_end().to_string(),
+                    severity: LintSeverity::Info,
+                    message: "sort is removed by using order-preserving variants of \
+                              operators below it"
+                        .to_string(),
+                    suggestion: None,
+                });
+            } else if !config.optimizer.prefer_existing_sort
+                && removes_sort(&permissive_rule, node)?
+            {
+                lints.push(Lint {
+                    sort: sort.trim_end().to_string(),
+                    severity: LintSeverity::Warning,
+                    message: "sort could be removed by using order-preserving \
+                              variants of operators below it"
+                        .to_string(),
+                    suggestion: Some(
+                        "enable datafusion.optimizer.prefer_existing_sort to remove \
+                         this sort"
+                            .to_string(),
+                    ),
+                });
+            }
+            Ok(TreeNodeRecursion::Continue)
+        })?;
+        Ok(lints)
+    }
+
+    /// Optimizes the given plan, reporting the progress to the given
+    /// `observer` if any. All the entry points of the rule go through this
+    /// driver, so they see the same plans: the nested plans are optimized,
+    /// shared subtrees are processed once, and the final merge output is
+    /// coalesced according to the config.
+    fn optimize_observed(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+        mut observer: Option<&mut (dyn OrderPreservingObserver + '_)>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let config = self.effective_config(config);
+        if let Some(observer) = observer.as_deref_mut() {
+            observer.started(self, &config);
+        }
+        let start = Instant::now();
+        let mut transformed_nodes = 0;
+        let optimize_node =
+            |opc: OrderPreservationContext,
+             decisions: Option<&mut Vec<SortRemovalDecision>>| {
+                let result = replace_with_order_preserving_variants_and_record(
+                    opc,
+                    self.is_spr_better,
+                    self.is_spm_better,
+                    &config,
+                    self.replacement_filter.as_deref(),
+                    self.hints(),
+                    decisions,
+                )?;
+                if result.transformed {
+                    transformed_nodes += 1;
+                }
+                Ok(result.data)
+            };
+        let optimized = OrderPreservingTraversal {
+            optimize_node,
+            hints: self.hints(),
+            observer,
+            optimized: HashMap::new(),
+            optimized_nested: HashMap::new(),
+            path: vec![],
+        }
+        .transform_up(Arc::clone(&plan))?
+        .plan;
+        if log_enabled!(Level::Debug) {
+            debug!(
+                "{} visited {} nodes and transformed {} of them in {:?}",
+                self.name(),
+                count_nodes(&plan, |_| true)?,
+                transformed_nodes,
+                start.elapsed()
             );
+        }
+        Ok(coalesce_final_merge_output(&plan, optimized, &config))
+    }
+
+    /// Optimizes the given plan like [`PhysicalOptimizerRule::optimize`], and
+    /// returns the optimized plan along with [`OrderPreservingCounts`] that
+    /// summarize how the plan has changed.
+    pub fn optimize_and_count(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<(Arc<dyn ExecutionPlan>, OrderPreservingCounts)> {
+        let optimized = self.optimize(Arc::clone(&plan), config)?;
+        let counts = OrderPreservingCounts::between(&plan, &optimized)?;
+        Ok((optimized, counts))
+    }
+}
+
+/// Observer of the [`ReplaceWithOrderPreservingVariants`] rule, see
+/// [`ReplaceWithOrderPreservingVariants::optimize_with_observer`]. The rule
+/// visits the nodes of a plan bottom-up, and consults the observer for every
+/// node it reaches. All methods have defaults that leave the optimization
+/// unchanged.
+pub trait OrderPreservingObserver {
+    /// Called before the given rule starts optimizing a plan with the given
+    /// config.
+    fn started(
+        &mut self,
+        _rule: &ReplaceWithOrderPreservingVariants,
+        _config: &ConfigOptions,
+    ) {
+    }
+
+    /// Returns the result of optimizing the subtree rooted at the given plan
+    /// node if it is already known, in which case the subtree is not visited.
+    fn cached(
+        &mut self,
+        _plan: &Arc<dyn ExecutionPlan>,
+    ) -> Option<OrderPreservationContext> {
+        None
+    }
+
+    /// Checks whether the rule should optimize the given plan node, whose
+    /// children are already optimized. If not, the node is left as it is.
+    fn should_optimize(&mut self, _plan: &Arc<dyn ExecutionPlan>) -> bool {
+        true
+    }
+
+    /// Called with the result of optimizing the subtree rooted at the
+    /// `original` plan node.
+    fn optimized(
+        &mut self,
+        _original: &Arc<dyn ExecutionPlan>,
+        _optimized: &OrderPreservationContext,
+    ) {
+    }
+
+    /// Called with the decision made by the rule about a [`SortExec`].
+    fn decided(&mut self, _decision: SortRemovalDecision) {}
+}
+
+/// Describes properties of plan nodes that the [`ReplaceWithOrderPreservingVariants`]
+/// rule can not derive from the [`ExecutionPlan`] itself, see
+/// [`ReplaceWithOrderPreservingVariants::with_hints`]. All methods have
+/// defaults that fit the built-in operators.
+pub trait OrderPreservingHints: fmt::Debug + Send + Sync {
+    /// Returns `true` if re-scanning the data produced by the given source is
+    /// expensive; e.g. because it is lazily materialized. The rule prefers
+    /// order-preserving variants over sorts that may spill and re-read their
+    /// input from such a source.
+    fn is_rescan_expensive(&self, _plan: &dyn ExecutionPlan) -> bool {
+        false
+    }
+
+    /// Returns `false` if the given node routes rows to its output partitions
+    /// non-deterministically; e.g. depending on the timing of its inputs. The
+    /// rule does not try to preserve orderings through such a node, as the
+    /// rows of its partitions may arrive in any order.
+    fn has_deterministic_partitioning(&self, _plan: &dyn ExecutionPlan) -> bool {
+        true
+    }
+
+    /// Returns `true` if the given node can produce the rows of each of its
+    /// partitions in the reverse order; e.g. a source that can read its sorted
+    /// data backwards without buffering it. The rule replaces a sort on the
+    /// reverse of the output ordering of such a node by a reverse scan.
+    fn can_reverse_scan(&self, _plan: &dyn ExecutionPlan) -> bool {
+        false
+    }
+
+    /// Returns a variant of the given node that produces the rows of each
+    /// partition in the reverse order, if it supports reverse scanning (see
+    /// [`Self::can_reverse_scan`]). Returns `None` otherwise.
+    fn with_reversed_scan(
+        &self,
+        _plan: &dyn ExecutionPlan,
+    ) -> Option<Arc<dyn ExecutionPlan>> {
+        None
+    }
+
+    /// Returns the plans that the given node executes on its own, besides its
+    /// children; e.g. the plan of a subquery that it evaluates. The rule
+    /// optimizes these plans before the node itself, and replaces them (see
+    /// [`Self::with_new_nested_plans`]) if they change.
+    fn nested_plans<'a>(
+        &self,
+        _plan: &'a dyn ExecutionPlan,
+    ) -> Vec<&'a Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    /// Returns a copy of the given node where the nested plans (see
+    /// [`Self::nested_plans`]) are replaced by the given ones, in the same
+    /// order. Hints that override [`Self::nested_plans`] must override this
+    /// method as well; the default implementation returns an error.
+    fn with_new_nested_plans(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _nested_plans: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        internal_err!("{} does not have nested plans", plan.name())
+    }
+}
+
+/// The default [`OrderPreservingHints`].
+#[derive(Debug)]
+struct NoHints;
+
+impl OrderPreservingHints for NoHints {}
+
+/// Collects the decisions made by the rule, see
+/// [`ReplaceWithOrderPreservingVariants::optimize_and_record_decisions`].
+impl OrderPreservingObserver for Vec<SortRemovalDecision> {
+    fn decided(&mut self, decision: SortRemovalDecision) {
+        self.push(decision);
+    }
+}
+
+/// Stops optimizing new nodes once the given instant passes, see
+/// [`ReplaceWithOrderPreservingVariants::optimize_with_deadline`].
+struct Deadline(Instant);
+
+impl OrderPreservingObserver for Deadline {
+    fn should_optimize(&mut self, _plan: &Arc<dyn ExecutionPlan>) -> bool {
+        Instant::now() < self.0
+    }
+}
+
+/// Number of operators removed or converted by the
+/// [`replace_with_order_preserving_variants`] sub-rule, see
+/// [`ReplaceWithOrderPreservingVariants::optimize_and_count`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OrderPreservingCounts {
+    /// Number of removed sorts (including [`PartialSortExec`]s)
+    pub sorts_removed: usize,
+    /// Number of [`RepartitionExec`]s converted to their order-preserving variant
+    pub repartitions_converted: usize,
+    /// Number of [`CoalescePartitionsExec`]s converted to [`SortPreservingMergeExec`]s
+    pub coalesces_converted: usize,
+    /// Whether the optimized plan is pipeline friendly, see [`is_pipeline_friendly`]
+    pub pipeline_friendly: bool,
+}
+
+impl OrderPreservingCounts {
+    /// Computes the counts by comparing the `original` plan with the
+    /// `optimized` one.
+    fn between(
+        original: &Arc<dyn ExecutionPlan>,
+        optimized: &Arc<dyn ExecutionPlan>,
+    ) -> Result<Self> {
+        let is_order_preserving_repartition = |plan: &Arc<dyn ExecutionPlan>| {
+            plan.as_any()
+                .downcast_ref::<RepartitionExec>()
+                .is_some_and(|repartition| repartition.preserve_order())
         };
+        let repartitions_before = count_nodes(original, is_order_preserving_repartition)?;
+        let repartitions_after = count_nodes(optimized, is_order_preserving_repartition)?;
+        let coalesces_before = count_nodes(original, is_coalesce_partitions)?;
+        let coalesces_after = count_nodes(optimized, is_coalesce_partitions)?;
+        Ok(Self {
+            sorts_removed: count_sorts(original)?.saturating_sub(count_sorts(optimized)?),
+            repartitions_converted: repartitions_after
+                .saturating_sub(repartitions_before),
+            coalesces_converted: coalesces_before.saturating_sub(coalesces_after),
+            pipeline_friendly: is_pipeline_friendly(optimized),
+        })
     }
+}
 
-    /// Runs the `replace_with_order_preserving_variants` sub-rule and asserts
-    /// the plan against the original and expected plans.
-    ///
-    /// # Parameters
+/// Checks whether the given plan can be executed without buffering any of its
+/// unbounded inputs, i.e. no [`SortExec`] remains on an unbounded branch of
+/// the plan.
+pub fn is_pipeline_friendly(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    let sorts_unbounded_input = plan
+        .as_any()
+        .downcast_ref::<SortExec>()
+        .is_some_and(|sort| sort.input().execution_mode().is_unbounded());
+    !sorts_unbounded_input && plan.children().into_iter().all(is_pipeline_friendly)
+}
+
+/// Downgrades every [`SortPreservingMergeExec`] whose output ordering is not
+/// required by any of its ancestors to a [`CoalescePartitionsExec`], as merging
+/// sorted streams is wasted work when nothing consumes the resulting ordering.
+///
+/// The root of `plan` is assumed to feed a consumer that doesn't care about the
+/// ordering of its input (e.g. an unordered sink). Merges with a fetch are kept
+/// as they are, since their fetch depends on the ordering.
+///
+/// The down-pass marks the nodes whose ordering is required by an ancestor in
+/// the data of their [`PlanContext`], and the up-pass replaces the unmarked
+/// merges.
+pub fn downgrade_unnecessary_order_preserving_merges(
+    plan: Arc<dyn ExecutionPlan>,
+) -> Result<Transformed<Arc<dyn ExecutionPlan>>> {
+    let is_unnecessary_merge = |opc: &PlanContext<bool>| {
+        !opc.data
+            && opc
+                .plan
+                .as_any()
+                .downcast_ref::<SortPreservingMergeExec>()
+                .is_some_and(|spm| spm.fetch().is_none())
+    };
+    PlanContext::<bool>::new_default(plan)
+        .transform_down(|mut opc| {
+            // The inputs of a merge that gets downgraded lose their consumer
+            // of ordering, as a `CoalescePartitionsExec` doesn't require any:
+            let is_downgraded = is_unnecessary_merge(&opc);
+            for (child, maintains, required_ordering) in izip!(
+                opc.children.iter_mut(),
+                opc.plan.maintains_input_order(),
+                opc.plan.required_input_ordering()
+            ) {
+                child.data = !is_downgraded
+                    && (required_ordering.is_some() || (opc.data && maintains));
+            }
+            Ok(Transformed::no(opc))
+        })?
+        .data
+        .transform_up(|mut opc| {
+            if !is_unnecessary_merge(&opc) {
+                return Ok(Transformed::no(opc));
+            }
+            let coalesce = CoalescePartitionsExec::new(Arc::clone(&opc.children[0].plan));
+            opc.plan = Arc::new(coalesce) as _;
+            Ok(Transformed::yes(opc))
+        })?
+        .map_data(|opc| Ok(opc.plan))
+}
+
+impl PhysicalOptimizerRule for ReplaceWithOrderPreservingVariants {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.optimize_observed(plan, config, None)
+    }
+
+    fn name(&self) -> &str {
+        "ReplaceWithOrderPreservingVariants"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+/// Bottom-up traversal of a plan that applies `optimize_node` (i.e. the
+/// [`replace_with_order_preserving_variants`] sub-rule) to its nodes. All the
+/// entry points of [`ReplaceWithOrderPreservingVariants`] go through it.
+struct OrderPreservingTraversal<'a, 'o, F> {
+    optimize_node: F,
+    hints: &'a dyn OrderPreservingHints,
+    observer: Option<&'a mut (dyn OrderPreservingObserver + 'o)>,
+    /// Results of the visited subtrees keyed by the addresses of their roots.
+    /// These addresses can not be reused while the traversed plan is alive.
+    optimized: HashMap<usize, OrderPreservationContext>,
+    /// Optimized versions of the visited nested plans keyed by their addresses,
+    /// or `None` while they are being optimized.
+    optimized_nested: HashMap<usize, Option<Arc<dyn ExecutionPlan>>>,
+    /// Child indices leading from the root of the traversed (nested) plan to
+    /// the node being visited
+    path: Vec<usize>,
+}
+
+impl<F> OrderPreservingTraversal<'_, '_, F>
+where
+    F: FnMut(
+        OrderPreservationContext,
+        Option<&mut Vec<SortRemovalDecision>>,
+    ) -> Result<OrderPreservationContext>,
+{
+    /// Applies `optimize_node` to the nodes of the given plan bottom-up, like a
+    /// `transform_up` over an [`OrderPreservationContext`] would, but processes
+    /// a subtree shared by multiple parents (i.e. reachable through the same
+    /// `Arc`) only once. Otherwise, the alternatives of a shared subtree would
+    /// be computed and rebuilt once per path leading to it, which grows
+    /// exponentially with the depth of the sharing. As the result of optimizing
+    /// a subtree only depends on the subtree itself, every parent receives a
+    /// copy of the same result; so the parents may still replace operators in
+    /// their own copies independently.
     ///
-    /// * `$EXPECTED_PLAN_LINES`: Expected input plan.
-    /// * `$EXPECTED_OPTIMIZED_PLAN_LINES`: Expected optimized plan.
-    /// * `$PLAN`: The plan to optimize.
-    /// * `$PREFER_EXISTING_SORT`: Value of the `prefer_existing_sort` flag.
-    macro_rules! assert_optimized {
-        ($EXPECTED_PLAN_LINES: expr, $EXPECTED_OPTIMIZED_PLAN_LINES: expr, $PLAN: expr, $PREFER_EXISTING_SORT: expr) => {
-            let physical_plan = $PLAN;
-            let formatted = displayable(physical_plan.as_ref()).indent(true).to_string();
-            let actual: Vec<&str> = formatted.trim().lines().collect();
+    /// The nested plans of a node (see [`OrderPreservingHints::nested_plans`]) are
+    /// optimized the same way before the node itself, as they are not children
+    /// of the node. The observer, if any, is consulted for every subtree that
+    /// is not shared.
+    fn transform_up(
+        &mut self,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Result<OrderPreservationContext> {
+        let key = Arc::as_ptr(&plan) as *const () as usize;
+        if let Some(opc) = self.optimized.get(&key) {
+            return Ok(clone_context(opc));
+        }
+        if let Some(opc) = self.observer.as_deref_mut().and_then(|o| o.cached(&plan)) {
+            self.optimized.insert(key, clone_context(&opc));
+            return Ok(opc);
+        }
+        let node = self.optimize_nested_plans(Arc::clone(&plan))?;
+        let mut children = vec![];
+        for (idx, child) in node.children().into_iter().enumerate() {
+            self.path.push(idx);
+            let child = self.transform_up(Arc::clone(child));
+            self.path.pop();
+            children.push(child?);
+        }
+        let opc = self.optimize_node(&plan, node, children)?;
+        self.optimized.insert(key, clone_context(&opc));
+        Ok(opc)
+    }
 
-            let expected_plan_lines: Vec<&str> = $EXPECTED_PLAN_LINES
-                .iter().map(|s| *s).collect();
+    /// Applies `optimize_node` to the given `node` of the plan, whose children
+    /// are already optimized, and reports it to the observer. `plan` is the
+    /// node as it was before the traversal.
+    fn optimize_node(
+        &mut self,
+        plan: &Arc<dyn ExecutionPlan>,
+        node: Arc<dyn ExecutionPlan>,
+        children: Vec<OrderPreservationContext>,
+    ) -> Result<OrderPreservationContext> {
+        let children_changed = children
+            .iter()
+            .zip(node.children())
+            .any(|(opc, child)| !Arc::ptr_eq(&opc.plan, child));
+        let mut opc = OrderPreservationContext::new(node, None, children);
+        if children_changed {
+            opc = opc.update_plan_from_children()?;
+        }
+        Ok(match self.observer.as_deref_mut() {
+            None => (self.optimize_node)(opc, None)?,
+            Some(observer) => {
+                let opc = if observer.should_optimize(&opc.plan) {
+                    let mut decisions = vec![];
+                    let opc = (self.optimize_node)(opc, Some(&mut decisions))?;
+                    for mut decision in decisions {
+                        decision.path.splice(0..0, self.path.iter().copied());
+                        observer.decided(decision);
+                    }
+                    opc
+                } else {
+                    opc
+                };
+                observer.optimized(plan, &opc);
+                opc
+            }
+        })
+    }
 
-            assert_eq!(
-                expected_plan_lines, actual,
-                "\n**Original Plan Mismatch\n\nexpected:\n\n{expected_plan_lines:#?}\nactual:\n\n{actual:#?}\n\n"
-            );
+    /// Replaces the nested plans of the given node with their optimized
+    /// versions. Each nested plan is only optimized once, even if multiple
+    /// nodes share it. A nested plan that is reached again while it is being
+    /// optimized is kept as is, so the recursion always terminates.
+    fn optimize_nested_plans(
+        &mut self,
+        node: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let hints = self.hints;
+        let nested_plans = hints.nested_plans(node.as_ref());
+        if nested_plans.is_empty() {
+            return Ok(node);
+        }
+        let mut changed = false;
+        let mut new_nested_plans = Vec::with_capacity(nested_plans.len());
+        for nested in nested_plans {
+            let key = Arc::as_ptr(nested) as *const () as usize;
+            let optimized = match self.optimized_nested.get(&key) {
+                Some(Some(optimized)) => Arc::clone(optimized),
+                Some(None) => Arc::clone(nested),
+                None => {
+                    self.optimized_nested.insert(key, None);
+                    let path = std::mem::take(&mut self.path);
+                    let optimized = self.transform_up(Arc::clone(nested));
+                    self.path = path;
+                    let optimized = optimized?.plan;
+                    self.optimized_nested
+                        .insert(key, Some(Arc::clone(&optimized)));
+                    optimized
+                }
+            };
+            changed |= !Arc::ptr_eq(&optimized, nested);
+            new_nested_plans.push(optimized);
+        }
+        if changed {
+            hints.with_new_nested_plans(Arc::clone(&node), new_nested_plans)
+        } else {
+            Ok(node)
+        }
+    }
+}
 
-            let expected_optimized_lines: Vec<&str> = $EXPECTED_OPTIMIZED_PLAN_LINES.iter().map(|s| *s).collect();
+/// Wraps the given `optimized` plan in a [`CoalesceBatchesExec`] if the
+/// `optimizer.coalesce_final_merge_output` config option is set and its root is
+/// a [`SortPreservingMergeExec`] introduced by the
+/// [`replace_with_order_preserving_variants`] sub-rule; i.e. the root of the
+/// `original` plan is not a merge. This way, the consumer of the single result
+/// stream receives batches of the configured batch size.
+pub(crate) fn coalesce_final_merge_output(
+    original: &Arc<dyn ExecutionPlan>,
+    optimized: Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> Arc<dyn ExecutionPlan> {
+    if config.optimizer.coalesce_final_merge_output
+        && is_sort_preserving_merge(&optimized)
+        && !is_sort_preserving_merge(original)
+    {
+        Arc::new(CoalesceBatchesExec::new(
+            optimized,
+            config.execution.batch_size,
+        ))
+    } else {
+        optimized
+    }
+}
 
-            // Run the rule top-down
-            let config = SessionConfig::new().with_prefer_existing_sort($PREFER_EXISTING_SORT);
-            let plan_with_pipeline_fixer = OrderPreservationContext::new_default(physical_plan);
-            let parallel = plan_with_pipeline_fixer.transform_up(|plan_with_pipeline_fixer| replace_with_order_preserving_variants(plan_with_pipeline_fixer, false, false, config.options())).data().and_then(check_integrity)?;
-            let optimized_physical_plan = parallel.plan;
+/// Origin of a sort in a physical plan, see [`count_sorts_by_origin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrigin {
+    /// The sort produces the ordering of the query result; e.g. it implements
+    /// a user-written `ORDER BY`.
+    OrderBy,
+    /// The sort satisfies the ordering requirement of an operator; e.g. it was
+    /// inserted by `EnforceSorting` below a sort-merge join.
+    Operator,
+}
+
+/// Number of sorts in a plan per [`SortOrigin`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SortOriginCounts {
+    /// Number of sorts with [`SortOrigin::OrderBy`]
+    pub order_by: usize,
+    /// Number of sorts with [`SortOrigin::Operator`]
+    pub operator: usize,
+}
+
+/// Counts the sorts, including the ones fused into other operators, in the
+/// given plan per [`SortOrigin`]. Sorts are not tagged with their origin when
+/// they are created, so the origin is derived from the position of the sort: A
+/// sort whose ordering reaches the root of the plan through operators that
+/// maintain it (without requiring it themselves) produces the ordering of the
+/// result. Any other sort satisfies the requirement of an operator above it.
+pub fn count_sorts_by_origin(plan: &Arc<dyn ExecutionPlan>) -> SortOriginCounts {
+    let mut counts = SortOriginCounts::default();
+    collect_sort_origins(plan, SortOrigin::OrderBy, &mut counts);
+    counts
+}
+
+fn collect_sort_origins(
+    plan: &Arc<dyn ExecutionPlan>,
+    origin: SortOrigin,
+    counts: &mut SortOriginCounts,
+) {
+    let is_sort = is_sort(plan) || plan.as_any().is::<PartialSortExec>();
+    if is_sort {
+        match origin {
+            SortOrigin::OrderBy => counts.order_by += 1,
+            SortOrigin::Operator => counts.operator += 1,
+        }
+    }
+    for ((child, maintains), required_ordering) in plan
+        .children()
+        .into_iter()
+        .zip(plan.maintains_input_order())
+        .zip(plan.required_input_ordering())
+    {
+        // A merge only combines the sorted partitions of its input, so it
+        // passes the origin of the sorts below it through:
+        let reaches_root = origin == SortOrigin::OrderBy
+            && !is_sort
+            && (is_sort_preserving_merge(plan)
+                || (maintains && required_ordering.is_none()));
+        let child_origin = if reaches_root {
+            SortOrigin::OrderBy
+        } else {
+            SortOrigin::Operator
+        };
+        collect_sort_origins(child, child_origin, counts);
+    }
+}
+
+/// Reason why the [`replace_with_order_preserving_variants`] sub-rule removed a
+/// sort, see [`RemovedSortId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortRemovalReason {
+    /// The input of the sort has at most one row, so it is trivially ordered
+    TriviallyOrderedInput,
+    /// The input of the sort already satisfies its ordering
+    OrderedInput,
+    /// The sort merges the partitions of a source that are sorted already, so
+    /// it was replaced by a merge of these partitions
+    MergedSortedPartitions,
+    /// The source below the sort is scanned backwards to produce the ordering
+    ReversedScan,
+    /// The sort was replaced by a merge of its input partitions sorted one by
+    /// one, see the `optimizer.parallelize_sort` config option
+    Parallelized,
+    /// The operators below the sort were replaced by their order-preserving
+    /// variants, which satisfy the ordering of the sort
+    OrderPreservingVariants,
+}
+
+impl fmt::Display for SortRemovalReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortRemovalReason::TriviallyOrderedInput => {
+                write!(f, "trivially_ordered_input")
+            }
+            SortRemovalReason::OrderedInput => write!(f, "ordered_input"),
+            SortRemovalReason::MergedSortedPartitions => {
+                write!(f, "merged_sorted_partitions")
+            }
+            SortRemovalReason::ReversedScan => write!(f, "reversed_scan"),
+            SortRemovalReason::Parallelized => write!(f, "parallelized"),
+            SortRemovalReason::OrderPreservingVariants => {
+                write!(f, "order_preserving_variants")
+            }
+        }
+    }
+}
+
+/// Reason why the [`replace_with_order_preserving_variants`] sub-rule kept a
+/// sort, see [`SortOutcome`]. When the order-preserving alternative of the sort
+/// input is declined for multiple reasons, the first one in the order of the
+/// variants below is reported.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SortKeptReason {
+    /// No operator below the sort loses the ordering that the sort requires,
+    /// so there is no order-preserving alternative to consider
+    NoOrderingConnection,
+    /// The sort expressions include a volatile expression, whose values can
+    /// not be preserved from the input
+    VolatileSortKey,
+    /// Converting (a part of) the input of the sort to order-preserving
+    /// variants failed, and the `optimizer.order_preserving_best_effort` config
+    /// option is set, so the failing part was left as it is
+    ConversionFailed {
+        /// Message of the first error
+        error: String,
+    },
+    /// The order-preserving alternative does not satisfy the ordering of the
+    /// sort
+    OrderingNotSatisfied,
+    /// The order-preserving alternative changes the output partitioning of the
+    /// sort
+    PartitioningChanged,
+    /// The sort has a fetch that the order-preserving alternative can not
+    /// apply, as it does not end with a merge
+    UnmergeableFetch,
+    /// The sort could be replaced by a merge of its sorted input partitions,
+    /// but the `optimizer.enable_order_preserving_coalesce` config option
+    /// disables such merges
+    MergeDisabled,
+    /// The sort could be replaced by a merge of its sorted input partitions,
+    /// but the replacement filter (see
+    /// [`ReplaceWithOrderPreservingVariants::with_replacement_filter`])
+    /// rejected the sort
+    ReplacementRejected,
+    /// Sorting the partitions in parallel is preferred over merging them, see
+    /// the `optimizer.order_preserving_parallelism_bias` config option
+    ParallelismBias,
+    /// The statistics show that the merges of the order-preserving
+    /// alternative are more expensive than the sort
+    MergingMoreExpensive,
+    /// The order-preserving alternative introduces a merge of `partitions`
+    /// partitions, which exceeds the fan-in `cap` set by the
+    /// `optimizer.max_order_preserving_merge_fanin` config option
+    BlockedByFaninCap {
+        /// Fan-in of the widest merge over the cap
+        partitions: usize,
+        /// Maximum fan-in of a merge
+        cap: usize,
+    },
+}
+
+/// Outcome of a [`SortRemovalDecision`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SortOutcome {
+    /// The sort was removed
+    Removed(SortRemovalReason),
+    /// The sort was kept, or it was only turned into a [`PartialSortExec`]
+    Kept(SortKeptReason),
+}
+
+/// Decision made by the [`replace_with_order_preserving_variants`] sub-rule
+/// about a [`SortExec`], see
+/// [`ReplaceWithOrderPreservingVariants::optimize_and_record_decisions`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortRemovalDecision {
+    /// Child indices leading from the root of the original plan to the sort.
+    /// For a sort in a nested plan (see [`OrderPreservingHints::nested_plans`]), the
+    /// path starts at the root of the nested plan instead. A sort in a subtree
+    /// shared by multiple parents is only reported once, along the first path
+    /// leading to it.
+    pub path: Vec<usize>,
+    /// Whether the sort was removed, and why
+    pub outcome: SortOutcome,
+}
+
+/// Stable identifier of a sort removed by the
+/// [`replace_with_order_preserving_variants`] sub-rule. It consists of the path
+/// of the sort in the original plan (i.e. the child indices leading from the
+/// root to the sort) and the reason of the removal. As such, the identifiers
+/// are reproducible across runs on the same plan with the same configuration,
+/// so systems that cache optimized plans can use them to detect when a config
+/// change affects the removed sorts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RemovedSortId {
+    /// Child indices leading from the root of the original plan to the sort
+    pub path: Vec<usize>,
+    /// Reason of the removal
+    pub reason: SortRemovalReason,
+}
+
+impl fmt::Display for RemovedSortId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for idx in &self.path {
+            write!(f, "/{idx}")?;
+        }
+        write!(f, ":{}", self.reason)
+    }
+}
+
+/// Estimates how much the cost of executing `original` changes when it is
+/// replaced by `optimized`, e.g. by this sub-rule. A negative value means that
+/// `optimized` is estimated to be cheaper. Only the operators that this
+/// sub-rule adds or removes are costed:
+/// - a [`SortExec`] costs `n * log2(n)` for its `n` input rows,
+/// - a [`SortPreservingMergeExec`] or an order-preserving [`RepartitionExec`]
+///   costs `n * log2(k)` for merging its `n` input rows from `k` partitions.
+///
+/// When the row count of an input is unknown, it is assumed to be a single
+/// batch (see `execution.batch_size`), regardless of how it is partitioned.
+pub fn estimated_cost_delta(
+    original: &Arc<dyn ExecutionPlan>,
+    optimized: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> f64 {
+    estimated_cost(optimized, config) - estimated_cost(original, config)
+}
+
+/// Checks whether the per-partition work of the given bounded `sort` should be
+/// kept instead of the merging work of the `alternate` plan that replaces the
+/// `sort` and its `original_input`, according to the config option
+/// `optimizer.order_preserving_parallelism_bias`. Both are weighed using the
+/// costs described in [`estimated_cost_delta`].
+fn prefers_parallel_sorts(
+    sort: &Arc<dyn ExecutionPlan>,
+    original_input: &Arc<dyn ExecutionPlan>,
+    alternate: &Arc<dyn ExecutionPlan>,
+    config: &ConfigOptions,
+) -> bool {
+    let bias = config.optimizer.order_preserving_parallelism_bias;
+    // Unbounded plans need the order-preserving variants to fix the pipeline:
+    if bias <= 0.0 || !sort.execution_mode().pipeline_friendly() {
+        return false;
+    }
+    let input_cost = estimated_cost(original_input, config);
+    let sort_cost = estimated_cost(sort, config) - input_cost;
+    let merge_cost = estimated_cost(alternate, config) - input_cost;
+    bias * merge_cost > (1.0 - bias) * sort_cost
+}
+
+/// Estimates the cost of the order-preserving `candidate` plan that replaces
+/// the given `sort` and its input, based on the row counts in the statistics of
+/// their inputs. Returns the cost of the merges in the `candidate` along with
+/// the cost of the `sort`, which are comparable:
+/// - merging `n` rows from `k` partitions (either by a [`SortPreservingMergeExec`]
+///   or an order-preserving [`RepartitionExec`]) costs `n * log2(k)`,
+/// - sorting `n` rows spread over `k` partitions costs `n * log2(n / k)`, as
+///   each partition is sorted on its own.
+///
+/// For large inputs sorting is more expensive, but with only a few rows per
+/// partition merging may be more expensive. Returns `None` if the statistics
+/// do not include the row count of any of these inputs.
+pub fn order_preserving_cost(
+    candidate: &Arc<dyn ExecutionPlan>,
+    sort: &SortExec,
+) -> Option<(f64, f64)> {
+    let input_rows = |input: &Arc<dyn ExecutionPlan>| {
+        let statistics = input.statistics().ok()?;
+        statistics.num_rows.get_value().map(|rows| *rows as f64)
+    };
+    let partition_count =
+        |input: &Arc<dyn ExecutionPlan>| input.output_partitioning().partition_count();
+
+    let rows = input_rows(sort.input())?;
+    let rows_per_partition = rows / partition_count(sort.input()) as f64;
+    let sort_cost = rows * rows_per_partition.max(2.0).log2();
+
+    let mut merge_cost = 0.0;
+    let mut has_unknown_rows = false;
+    candidate
+        .apply(|node| {
+            let merged_input = if let Some(merge) =
+                node.as_any().downcast_ref::<SortPreservingMergeExec>()
+            {
+                Some(merge.input())
+            } else {
+                node.as_any()
+                    .downcast_ref::<RepartitionExec>()
+                    .filter(|repartition| repartition.preserve_order())
+                    .map(|repartition| repartition.input())
+            };
+            if let Some(input) = merged_input {
+                let Some(rows) = input_rows(input) else {
+                    has_unknown_rows = true;
+                    return Ok(TreeNodeRecursion::Stop);
+                };
+                merge_cost += rows * (partition_count(input).max(2) as f64).log2();
+            }
+            Ok(TreeNodeRecursion::Continue)
+        })
+        .ok()?;
+    (!has_unknown_rows).then_some((merge_cost, sort_cost))
+}
+
+/// Checks whether the merges of the `alternate` plan that replaces the given
+/// bounded `sort` are estimated to be more expensive than the `sort` itself (see
+/// [`order_preserving_cost`]).
+fn is_merging_more_expensive(
+    sort_plan: &Arc<dyn ExecutionPlan>,
+    alternate: &Arc<dyn ExecutionPlan>,
+    sort: &SortExec,
+) -> bool {
+    // Unbounded plans need the order-preserving variants to fix the pipeline:
+    sort_plan.execution_mode().pipeline_friendly()
+        && order_preserving_cost(alternate, sort)
+            .is_some_and(|(merge_cost, sort_cost)| merge_cost > sort_cost)
+}
+
+/// Sums the estimated costs of the operators of the given plan as described in
+/// [`estimated_cost_delta`].
+fn estimated_cost(plan: &Arc<dyn ExecutionPlan>, config: &ConfigOptions) -> f64 {
+    let input_rows = |input: &Arc<dyn ExecutionPlan>| {
+        let rows = input
+            .statistics()
+            .ok()
+            .and_then(|statistics| statistics.num_rows.get_value().copied())
+            .unwrap_or(config.execution.batch_size);
+        rows as f64
+    };
+    let merge_cost = |input: &Arc<dyn ExecutionPlan>| {
+        let partitions = input.output_partitioning().partition_count().max(2);
+        input_rows(input) * (partitions as f64).log2()
+    };
+    let cost = if let Some(sort) = plan.as_any().downcast_ref::<SortExec>() {
+        let rows = input_rows(sort.input());
+        rows * rows.max(2.0).log2()
+    } else if let Some(merge) = plan.as_any().downcast_ref::<SortPreservingMergeExec>() {
+        merge_cost(merge.input())
+    } else if let Some(repartition) = plan
+        .as_any()
+        .downcast_ref::<RepartitionExec>()
+        .filter(|repartition| repartition.preserve_order())
+    {
+        merge_cost(repartition.input())
+    } else {
+        0.0
+    };
+    cost + plan
+        .children()
+        .into_iter()
+        .map(|child| estimated_cost(child, config))
+        .sum::<f64>()
+}
+
+/// A cache of the subtrees that the [`ReplaceWithOrderPreservingVariants`]
+/// rule has already processed and left unchanged. Subtrees are identified by
+/// the address of their root, so a cache only helps when the very same plan
+/// nodes are optimized again; e.g. by planners that re-optimize large shared
+/// plans incrementally. Pass the cache to
+/// [`ReplaceWithOrderPreservingVariants::optimize_with_observer`] to skip the
+/// cached subtrees. As the rule runs bottom-up, the result of optimizing a
+/// subtree (including its order-preservation data) only depends on the
+/// subtree itself and on the settings of the rule and the config options it
+/// runs with. The cache is cleared whenever these settings change, so
+/// skipping cached subtrees does not change the optimized plan.
+#[derive(Debug, Default)]
+pub struct UnchangedSubtreeCache {
+    /// Order-preservation data of the unchanged subtrees keyed by the address
+    /// of their roots. The cached contexts also keep the roots alive, so that
+    /// their addresses can not be reused by other plans.
+    subtrees: HashMap<usize, OrderPreservationContext>,
+    /// Fingerprint of the rule settings and config options that the cached
+    /// subtrees were optimized with.
+    fingerprint: Option<String>,
+}
+
+impl UnchangedSubtreeCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cached subtrees.
+    pub fn len(&self) -> usize {
+        self.subtrees.len()
+    }
+
+    /// Checks whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.subtrees.is_empty()
+    }
+}
+
+impl OrderPreservingObserver for UnchangedSubtreeCache {
+    fn started(
+        &mut self,
+        rule: &ReplaceWithOrderPreservingVariants,
+        config: &ConfigOptions,
+    ) {
+        // The replacement filter is only identified by its address, as
+        // closures can not be compared otherwise:
+        let filter = rule
+            .replacement_filter
+            .as_ref()
+            .map(|filter| Arc::as_ptr(filter) as *const () as usize);
+        let mut fingerprint = format!("{rule:?} {filter:?}");
+        for entry in config.entries() {
+            fingerprint.push_str(&format!("\n{}={:?}", entry.key, entry.value));
+        }
+        if self.fingerprint.as_ref() != Some(&fingerprint) {
+            self.subtrees.clear();
+            self.fingerprint = Some(fingerprint);
+        }
+    }
+
+    fn cached(
+        &mut self,
+        plan: &Arc<dyn ExecutionPlan>,
+    ) -> Option<OrderPreservationContext> {
+        let key = Arc::as_ptr(plan) as *const () as usize;
+        self.subtrees.get(&key).map(clone_context)
+    }
+
+    fn optimized(
+        &mut self,
+        original: &Arc<dyn ExecutionPlan>,
+        optimized: &OrderPreservationContext,
+    ) {
+        if Arc::ptr_eq(&optimized.plan, original) {
+            let key = Arc::as_ptr(original) as *const () as usize;
+            self.subtrees.insert(key, clone_context(optimized));
+        }
+    }
+}
+
+/// Deep-copies the given order-preservation context.
+fn clone_context(opc: &OrderPreservationContext) -> OrderPreservationContext {
+    let children = opc.children.iter().map(clone_context).collect();
+    OrderPreservationContext::new(Arc::clone(&opc.plan), opc.data.clone(), children)
+}
+
+/// Counts the sorts, including the ones fused into other operators, in the
+/// given plan.
+fn count_sorts(plan: &Arc<dyn ExecutionPlan>) -> Result<usize> {
+    count_nodes(plan, |node| {
+        is_sort(node) || node.as_any().is::<PartialSortExec>()
+    })
+}
+
+/// Counts the nodes of the given plan that match the given predicate.
+fn count_nodes(
+    plan: &Arc<dyn ExecutionPlan>,
+    predicate: impl Fn(&Arc<dyn ExecutionPlan>) -> bool,
+) -> Result<usize> {
+    let mut count = 0;
+    plan.apply(|node| {
+        if predicate(node) {
+            count += 1;
+        }
+        Ok(TreeNodeRecursion::Continue)
+    })?;
+    Ok(count)
+}
+
+/// Severity of a [`Lint`] reported by
+/// [`ReplaceWithOrderPreservingVariants::lint_order_preserving`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintSeverity {
+    /// The optimization is applied with the current configuration
+    Info,
+    /// The optimization is missed with the current configuration
+    Warning,
+}
+
+/// A finding of [`ReplaceWithOrderPreservingVariants::lint_order_preserving`]
+/// about a `SortExec` in a plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// One-line description of the `SortExec` the lint is about
+    pub sort: String,
+    /// Severity of the lint
+    pub severity: LintSeverity,
+    /// Description of the finding
+    pub message: String,
+    /// Suggested configuration change, if any
+    pub suggestion: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::any::Any;
+    use std::fmt::Formatter;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::datasource::file_format::file_compression_type::FileCompressionType;
+    use crate::datasource::listing::PartitionedFile;
+    use crate::datasource::physical_plan::{CsvExec, FileScanConfig};
+    use crate::datasource::MemTable;
+    use crate::physical_optimizer::limit_pushdown::LimitPushdown;
+    use crate::physical_optimizer::test_utils::{
+        bounded_window_exec, check_integrity, plans_structurally_equal,
+        sort_merge_join_exec, PassThroughTestExec,
+    };
+    use crate::physical_plan::aggregates::{
+        AggregateExec, AggregateMode, PhysicalGroupBy,
+    };
+    use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+    use crate::physical_plan::filter::FilterExec;
+    use crate::physical_plan::insert::DataSink;
+    use crate::physical_plan::joins::{
+        CrossJoinExec, PartitionMode, StreamJoinPartitionMode, SymmetricHashJoinExec,
+    };
+    use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::metrics::MetricsSet;
+    use crate::physical_plan::projection::ProjectionExec;
+    use crate::physical_plan::sorts::sort::SortExec;
+    use crate::physical_plan::union::InterleaveExec;
+    use crate::physical_plan::unnest::UnnestExec;
+    use crate::physical_plan::windows::{create_window_expr, BoundedWindowAggExec};
+    use crate::physical_plan::InputOrderMode;
+    use crate::physical_plan::{
+        collect, get_plan_string, DisplayAs, DisplayFormatType, ExecutionPlan,
+        Partitioning,
+    };
+    use crate::prelude::{SessionConfig, SessionContext};
+    use crate::test::TestStreamPartition;
+
+    use arrow::array::{DictionaryArray, Int32Array, StringArray};
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Int32Type, Schema, SchemaRef};
+    use arrow::record_batch::RecordBatch;
+    use datafusion_common::{assert_batches_eq, internal_err, Result, UnnestOptions};
+    use datafusion_execution::memory_pool::{GreedyMemoryPool, MemoryPool};
+    use datafusion_execution::object_store::ObjectStoreUrl;
+    use datafusion_execution::runtime_env::RuntimeEnvBuilder;
+    use datafusion_execution::{SendableRecordBatchStream, TaskContext};
+    use datafusion_expr::{JoinType, Operator, WindowFrame, WindowFunctionDefinition};
+    use datafusion_functions_aggregate::array_agg::array_agg_udaf;
+    use datafusion_functions_window::row_number::row_number_udwf;
+    use datafusion_physical_expr::aggregate::AggregateExprBuilder;
+    use datafusion_physical_expr::expressions::{self, col, Column};
+    use datafusion_physical_expr::{reverse_order_bys, ConstExpr, LexRequirement};
+    use datafusion_physical_plan::streaming::StreamingTableExec;
+
+    use async_trait::async_trait;
+    use rstest::rstest;
+
+    /// Runs the `replace_with_order_preserving_variants` sub-rule and asserts
+    /// the plan against the original and expected plans for both bounded and
+    /// unbounded cases.
+    ///
+    /// # Parameters
+    ///
+    /// * `EXPECTED_UNBOUNDED_PLAN_LINES`: Expected input unbounded plan.
+    /// * `EXPECTED_BOUNDED_PLAN_LINES`: Expected input bounded plan.
+    /// * `EXPECTED_UNBOUNDED_OPTIMIZED_PLAN_LINES`: Optimized plan, which is
+    ///   the same regardless of the value of the `prefer_existing_sort` flag.
+    /// * `EXPECTED_BOUNDED_OPTIMIZED_PLAN_LINES`: Optimized plan when the flag
+    ///   `prefer_existing_sort` is `false` for bounded cases.
+    /// * `EXPECTED_BOUNDED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES`: Optimized plan
+    ///   when the flag `prefer_existing_sort` is `true` for bounded cases.
+    /// * `$PLAN`: The plan to optimize.
+    /// * `$SOURCE_UNBOUNDED`: Whether the given plan contains an unbounded source.
+    macro_rules! assert_optimized_in_all_boundedness_situations {
+        ($EXPECTED_UNBOUNDED_PLAN_LINES: expr,  $EXPECTED_BOUNDED_PLAN_LINES: expr, $EXPECTED_UNBOUNDED_OPTIMIZED_PLAN_LINES: expr, $EXPECTED_BOUNDED_OPTIMIZED_PLAN_LINES: expr, $EXPECTED_BOUNDED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES: expr, $PLAN: expr, $SOURCE_UNBOUNDED: expr) => {
+            if $SOURCE_UNBOUNDED {
+                assert_optimized_prefer_sort_on_off!(
+                    $EXPECTED_UNBOUNDED_PLAN_LINES,
+                    $EXPECTED_UNBOUNDED_OPTIMIZED_PLAN_LINES,
+                    $EXPECTED_UNBOUNDED_OPTIMIZED_PLAN_LINES,
+                    $PLAN
+                );
+            } else {
+                assert_optimized_prefer_sort_on_off!(
+                    $EXPECTED_BOUNDED_PLAN_LINES,
+                    $EXPECTED_BOUNDED_OPTIMIZED_PLAN_LINES,
+                    $EXPECTED_BOUNDED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES,
+                    $PLAN
+                );
+            }
+        };
+    }
+
+    /// Runs the `replace_with_order_preserving_variants` sub-rule and asserts
+    /// the plan against the original and expected plans.
+    ///
+    /// # Parameters
+    ///
+    /// * `$EXPECTED_PLAN_LINES`: Expected input plan.
+    /// * `EXPECTED_OPTIMIZED_PLAN_LINES`: Optimized plan when the flag
+    ///   `prefer_existing_sort` is `false`.
+    /// * `EXPECTED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES`: Optimized plan when
+    ///   the flag `prefer_existing_sort` is `true`.
+    /// * `$PLAN`: The plan to optimize.
+    macro_rules! assert_optimized_prefer_sort_on_off {
+        ($EXPECTED_PLAN_LINES: expr, $EXPECTED_OPTIMIZED_PLAN_LINES: expr, $EXPECTED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES: expr, $PLAN: expr) => {
+            assert_optimized!(
+                $EXPECTED_PLAN_LINES,
+                $EXPECTED_OPTIMIZED_PLAN_LINES,
+                $PLAN.clone(),
+                false
+            );
+            assert_optimized!(
+                $EXPECTED_PLAN_LINES,
+                $EXPECTED_PREFER_SORT_ON_OPTIMIZED_PLAN_LINES,
+                $PLAN,
+                true
+            );
+        };
+    }
+
+    /// Runs the `replace_with_order_preserving_variants` sub-rule and asserts
+    /// the plan against the original and expected plans.
+    ///
+    /// # Parameters
+    ///
+    /// * `$EXPECTED_PLAN_LINES`: Expected input plan.
+    /// * `$EXPECTED_OPTIMIZED_PLAN_LINES`: Expected optimized plan.
+    /// * `$PLAN`: The plan to optimize.
+    /// * `$PREFER_EXISTING_SORT`: Value of the `prefer_existing_sort` flag.
+    macro_rules! assert_optimized {
+        ($EXPECTED_PLAN_LINES: expr, $EXPECTED_OPTIMIZED_PLAN_LINES: expr, $PLAN: expr, $PREFER_EXISTING_SORT: expr) => {
+            let physical_plan = $PLAN;
+            let formatted = displayable(physical_plan.as_ref()).indent(true).to_string();
+            let actual: Vec<&str> = formatted.trim().lines().collect();
+
+            let expected_plan_lines: Vec<&str> = $EXPECTED_PLAN_LINES
+                .iter().map(|s| *s).collect();
+
+            assert_eq!(
+                expected_plan_lines, actual,
+                "\n**Original Plan Mismatch\n\nexpected:\n\n{expected_plan_lines:#?}\nactual:\n\n{actual:#?}\n\n"
+            );
+
+            let expected_optimized_lines: Vec<&str> = $EXPECTED_OPTIMIZED_PLAN_LINES.iter().map(|s| *s).collect();
+
+            // Run the rule top-down
+            let config = SessionConfig::new().with_prefer_existing_sort($PREFER_EXISTING_SORT);
+            let plan_with_pipeline_fixer = OrderPreservationContext::new_default(physical_plan);
+            let parallel = plan_with_pipeline_fixer.transform_up(|plan_with_pipeline_fixer| replace_with_order_preserving_variants(plan_with_pipeline_fixer, false, false, config.options())).data().and_then(check_integrity)?;
+            let optimized_physical_plan = parallel.plan;
+
+            // Get string representation of the plan
+            let actual = get_plan_string(&optimized_physical_plan);
+            assert_eq!(
+                expected_optimized_lines, actual,
+                "\n**Optimized Plan Mismatch\n\nexpected:\n\n{expected_optimized_lines:#?}\nactual:\n\n{actual:#?}\n\n"
+            );
+        };
+    }
+
+    #[rstest]
+    #[tokio::test]
+    // Searches for a simple sort and a repartition just after it, the second repartition with 1 input partition should not be affected
+    async fn test_replace_multiple_input_repartition_1(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition = repartition_exec_hash(repartition_exec_round_robin(source));
+        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition, true);
+
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan.clone(),
+            source_unbounded
+        );
+
+        // Removing the sort does not change the cardinality of the plan:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(Arc::clone(&physical_plan), &config)?;
+        assert!(!get_plan_string(&optimized)
+            .iter()
+            .any(|line| line.contains("SortExec")));
+        assert_eq!(
+            optimized.statistics()?.num_rows,
+            physical_plan.statistics()?.num_rows
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_inter_children_change_only(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr_default("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let sort = sort_exec(
+            vec![sort_expr_default("a", &coalesce_partitions.schema())],
+            coalesce_partitions,
+            false,
+        );
+        let repartition_rr2 = repartition_exec_round_robin(sort);
+        let repartition_hash2 = repartition_exec_hash(repartition_rr2);
+        let filter = filter_exec(repartition_hash2);
+        let sort2 =
+            sort_exec(vec![sort_expr_default("a", &filter.schema())], filter, true);
+
+        let physical_plan = sort_preserving_merge_exec(
+            vec![sort_expr_default("a", &sort2.schema())],
+            sort2,
+        );
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          SortExec: expr=[a@0 ASC], preserve_partitioning=[false]",
+            "            CoalescePartitionsExec",
+            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                  StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC]",
+        ];
+        let expected_input_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          SortExec: expr=[a@0 ASC], preserve_partitioning=[false]",
+            "            CoalescePartitionsExec",
+            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  FilterExec: c@1 > 3",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        SortPreservingMergeExec: [a@0 ASC]",
+            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC",
+            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "              StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          SortExec: expr=[a@0 ASC], preserve_partitioning=[false]",
+            "            CoalescePartitionsExec",
+            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  FilterExec: c@1 > 3",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        SortPreservingMergeExec: [a@0 ASC]",
+            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC",
+            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_replace_multiple_input_repartition_2(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let filter = filter_exec(repartition_rr);
+        let repartition_hash = repartition_exec_hash(filter);
+        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition_hash, true);
+
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded =  [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded =  [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded =  [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_replace_multiple_input_repartition_with_extra_steps(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let filter = filter_exec(repartition_hash);
+        let coalesce_batches_exec: Arc<dyn ExecutionPlan> = coalesce_batches_exec(filter);
+        let sort = sort_exec(vec![sort_expr("a", &schema)], coalesce_batches_exec, true);
+
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    CoalesceBatchesExec: target_batch_size=8192",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    CoalesceBatchesExec: target_batch_size=8192",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    CoalesceBatchesExec: target_batch_size=8192",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_replace_multiple_input_repartition_with_extra_steps_2(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let coalesce_batches_exec_1 = coalesce_batches_exec(repartition_rr);
+        let repartition_hash = repartition_exec_hash(coalesce_batches_exec_1);
+        let filter = filter_exec(repartition_hash);
+        let coalesce_batches_exec_2 = coalesce_batches_exec(filter);
+        let sort =
+            sort_exec(vec![sort_expr("a", &schema)], coalesce_batches_exec_2, true);
+
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    CoalesceBatchesExec: target_batch_size=8192",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          CoalesceBatchesExec: target_batch_size=8192",
+            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "              StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    CoalesceBatchesExec: target_batch_size=8192",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          CoalesceBatchesExec: target_batch_size=8192",
+            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "        CoalesceBatchesExec: target_batch_size=8192",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    CoalesceBatchesExec: target_batch_size=8192",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          CoalesceBatchesExec: target_batch_size=8192",
+            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "        CoalesceBatchesExec: target_batch_size=8192",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_not_replacing_when_no_need_to_preserve_sorting(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let filter = filter_exec(repartition_hash);
+        let coalesce_batches_exec: Arc<dyn ExecutionPlan> = coalesce_batches_exec(filter);
+
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            coalesce_partitions_exec(coalesce_batches_exec);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "CoalescePartitionsExec",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "CoalescePartitionsExec",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "CoalescePartitionsExec",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results same with and without flag, because there is no executor  with ordering requirement
+        let expected_optimized_bounded = [
+            "CoalescePartitionsExec",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = expected_optimized_bounded;
+
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_multiple_replacable_repartitions(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let filter = filter_exec(repartition_hash);
+        let coalesce_batches = coalesce_batches_exec(filter);
+        let repartition_hash_2 = repartition_exec_hash(coalesce_batches);
+        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition_hash_2, true);
+
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      CoalesceBatchesExec: target_batch_size=8192",
+            "        FilterExec: c@1 > 3",
+            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "              StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      CoalesceBatchesExec: target_batch_size=8192",
+            "        FilterExec: c@1 > 3",
+            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    CoalesceBatchesExec: target_batch_size=8192",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      CoalesceBatchesExec: target_batch_size=8192",
+            "        FilterExec: c@1 > 3",
+            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    CoalesceBatchesExec: target_batch_size=8192",
+            "      FilterExec: c@1 > 3",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_not_replace_with_different_orderings(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(
+            vec![sort_expr_default("c", &repartition_hash.schema())],
+            repartition_hash,
+            true,
+        );
+
+        let physical_plan = sort_preserving_merge_exec(
+            vec![sort_expr_default("c", &sort.schema())],
+            sort,
+        );
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results same with and without flag, because ordering requirement of the executor is different than the existing ordering.
+        let expected_optimized_bounded = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = expected_optimized_bounded;
+
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_lost_ordering(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan =
+            sort_exec(vec![sort_expr("a", &schema)], coalesce_partitions, false);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan.clone(),
+            source_unbounded
+        );
+
+        // The dry run returns the alternative even if it is not accepted:
+        let alternative = ReplaceWithOrderPreservingVariants::new(false, false)
+            .compute_order_preserving_alternative(&physical_plan, &ConfigOptions::new())?
+            .unwrap();
+        let expected_alternative = if source_unbounded {
+            expected_optimized_unbounded
+        } else {
+            expected_optimized_bounded_sort_preserve
+        };
+        assert_eq!(get_plan_string(&alternative), expected_alternative);
+        let expected_input = if source_unbounded {
+            expected_input_unbounded
+        } else {
+            expected_input_bounded
+        };
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        if source_unbounded {
+            // Merging is estimated to be cheaper than sorting all rows again:
+            let config = ConfigOptions::new();
+            let optimized = optimize_with_config(Arc::clone(&physical_plan), &config)?;
+            assert!(estimated_cost_delta(&physical_plan, &optimized, &config) < 0.0);
+        }
+
+        // Removing the sort makes the unbounded plan pipeline friendly:
+        assert_eq!(is_pipeline_friendly(&physical_plan), !source_unbounded);
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false);
+        let (_, counts) =
+            rule.optimize_and_count(Arc::clone(&physical_plan), &ConfigOptions::new())?;
+        assert!(counts.pipeline_friendly);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_lost_ordering_through_interleave(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let interleave_input = || {
+            let source = if source_unbounded {
+                stream_exec_ordered(&schema, sort_exprs.clone())
+            } else {
+                csv_exec_sorted(&schema, sort_exprs.clone())
+            };
+            let repartition_rr = repartition_exec_round_robin(source);
+            repartition_exec_hash(repartition_rr)
+        };
+        let interleave = Arc::new(InterleaveExec::try_new(vec![
+            interleave_input(),
+            interleave_input(),
+        ])?);
+        let coalesce_partitions = coalesce_partitions_exec(interleave);
+        let physical_plan =
+            sort_exec(vec![sort_expr("a", &schema)], coalesce_partitions, false);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    InterleaveExec",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    InterleaveExec",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  UnionExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = expected_input_bounded;
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  UnionExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_lost_and_kept_ordering(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, sort_exprs)
+        };
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let sort = sort_exec(
+            vec![sort_expr_default("c", &coalesce_partitions.schema())],
+            coalesce_partitions,
+            false,
+        );
+        let repartition_rr2 = repartition_exec_round_robin(sort);
+        let repartition_hash2 = repartition_exec_hash(repartition_rr2);
+        let filter = filter_exec(repartition_hash2);
+        let sort2 =
+            sort_exec(vec![sort_expr_default("c", &filter.schema())], filter, true);
+
+        let physical_plan = sort_preserving_merge_exec(
+            vec![sort_expr_default("c", &sort2.schema())],
+            sort2,
+        );
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
+            "            CoalescePartitionsExec",
+            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                  StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
+            "            CoalescePartitionsExec",
+            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  FilterExec: c@1 > 3",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=c@1 ASC",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
+            "          CoalescePartitionsExec",
+            "            RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "              RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
+            "    FilterExec: c@1 > 3",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
+            "            CoalescePartitionsExec",
+            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  FilterExec: c@1 > 3",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=c@1 ASC",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
+            "          CoalescePartitionsExec",
+            "            RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "              RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_removed_sort_ids_with_lost_and_kept_ordering() -> Result<()> {
+        // Builds the plan of `test_with_lost_and_kept_ordering` with an
+        // unbounded source:
+        let build_plan = || -> Result<Arc<dyn ExecutionPlan>> {
+            let schema = create_test_schema()?;
+            let sort_exprs = vec![sort_expr("a", &schema)];
+            let source = stream_exec_ordered(&schema, sort_exprs);
+            let repartition_rr = repartition_exec_round_robin(source);
+            let repartition_hash = repartition_exec_hash(repartition_rr);
+            let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+            let sort = sort_exec(
+                vec![sort_expr_default("c", &coalesce_partitions.schema())],
+                coalesce_partitions,
+                false,
+            );
+            let repartition_rr2 = repartition_exec_round_robin(sort);
+            let repartition_hash2 = repartition_exec_hash(repartition_rr2);
+            let filter = filter_exec(repartition_hash2);
+            let sort2 =
+                sort_exec(vec![sort_expr_default("c", &filter.schema())], filter, true);
+            Ok(sort_preserving_merge_exec(
+                vec![sort_expr_default("c", &sort2.schema())],
+                sort2,
+            ))
+        };
+
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false);
+        let config = ConfigOptions::new();
+        let (_, removed_sorts) =
+            rule.optimize_and_collect_removed_sorts(build_plan()?, &config)?;
+        let (_, removed_sorts_rerun) =
+            rule.optimize_and_collect_removed_sorts(build_plan()?, &config)?;
+        assert_eq!(removed_sorts, removed_sorts_rerun);
+        assert_eq!(
+            removed_sorts,
+            vec![RemovedSortId {
+                path: vec![0],
+                reason: SortRemovalReason::OrderPreservingVariants,
+            }]
+        );
+        assert_eq!(removed_sorts[0].to_string(), "/0:order_preserving_variants");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_removed_sort_ids_with_ordered_input() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        // The source already satisfies the ordering of the sort, so no
+        // operator needs to be converted:
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let sort = sort_exec(sort_exprs.clone(), source, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let (_, removed_sorts) = ReplaceWithOrderPreservingVariants::new(false, false)
+            .optimize_and_collect_removed_sorts(physical_plan, &ConfigOptions::new())?;
+        assert_eq!(
+            removed_sorts,
+            vec![RemovedSortId {
+                path: vec![0],
+                reason: SortRemovalReason::OrderedInput,
+            }]
+        );
+        assert_eq!(removed_sorts[0].to_string(), "/0:ordered_input");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dump_order_connections_with_lost_and_kept_ordering() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = stream_exec_ordered(&schema, sort_exprs);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let sort = sort_exec(
+            vec![sort_expr_default("c", &coalesce_partitions.schema())],
+            coalesce_partitions,
+            false,
+        );
+        let repartition_rr2 = repartition_exec_round_robin(sort);
+        let repartition_hash2 = repartition_exec_hash(repartition_rr2);
+        let filter = filter_exec(repartition_hash2);
+        let sort2 =
+            sort_exec(vec![sort_expr_default("c", &filter.schema())], filter, true);
+        let physical_plan = sort_preserving_merge_exec(
+            vec![sort_expr_default("c", &sort2.schema())],
+            sort2,
+        );
+
+        // The connection initiated by the inner `RepartitionExec` reaches the
+        // inner `SortExec`, but breaks at the inner `CoalescePartitionsExec`
+        // afterwards, as the `SortExec` does not maintain ordering:
+        let expected = [
+            "SortPreservingMergeExec: [c@1 ASC], ordering_connection=false, children_ordering_connections=[false]",
+            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true], ordering_connection=false, children_ordering_connections=[true]",
+            "    FilterExec: c@1 > 3, ordering_connection=false, children_ordering_connections=[true]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, ordering_connection=true, children_ordering_connections=[false]",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1, ordering_connection=false, children_ordering_connections=[false]",
+            "          SortExec: expr=[c@1 ASC], preserve_partitioning=[false], ordering_connection=false, children_ordering_connections=[true]",
+            "            CoalescePartitionsExec, ordering_connection=false, children_ordering_connections=[true]",
+            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, ordering_connection=true, children_ordering_connections=[false]",
+            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1, ordering_connection=false, children_ordering_connections=[false]",
+            "                  StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST], ordering_connection=false, children_ordering_connections=[]",
+        ];
+        let dump = dump_order_connections(&physical_plan);
+        let actual = dump.lines().collect::<Vec<_>>();
+        assert_eq!(actual, expected, "\n{dump}");
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_multiple_child_trees(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+
+        let left_sort_exprs = vec![sort_expr("a", &schema)];
+        let left_source = if source_unbounded {
+            stream_exec_ordered(&schema, left_sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, left_sort_exprs)
+        };
+        let left_repartition_rr = repartition_exec_round_robin(left_source);
+        let left_repartition_hash = repartition_exec_hash(left_repartition_rr);
+        let left_coalesce_partitions =
+            Arc::new(CoalesceBatchesExec::new(left_repartition_hash, 4096));
+
+        let right_sort_exprs = vec![sort_expr("a", &schema)];
+        let right_source = if source_unbounded {
+            stream_exec_ordered(&schema, right_sort_exprs)
+        } else {
+            csv_exec_sorted(&schema, right_sort_exprs)
+        };
+        let right_repartition_rr = repartition_exec_round_robin(right_source);
+        let right_repartition_hash = repartition_exec_hash(right_repartition_rr);
+        let right_coalesce_partitions =
+            Arc::new(CoalesceBatchesExec::new(right_repartition_hash, 4096));
+
+        let hash_join_exec =
+            hash_join_exec(left_coalesce_partitions, right_coalesce_partitions);
+        let sort = sort_exec(
+            vec![sort_expr_default("a", &hash_join_exec.schema())],
+            hash_join_exec,
+            true,
+        );
+
+        let physical_plan = sort_preserving_merge_exec(
+            vec![sort_expr_default("a", &sort.schema())],
+            sort,
+        );
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "      CoalesceBatchesExec: target_batch_size=4096",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "      CoalesceBatchesExec: target_batch_size=4096",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "      CoalesceBatchesExec: target_batch_size=4096",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      CoalesceBatchesExec: target_batch_size=4096",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "      CoalesceBatchesExec: target_batch_size=4096",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "      CoalesceBatchesExec: target_batch_size=4096",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results same with and without flag, because ordering get lost during intermediate executor anyway. Hence no need to preserve
+        // existing ordering.
+        let expected_optimized_bounded = [
+            "SortPreservingMergeExec: [a@0 ASC]",
+            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "      CoalesceBatchesExec: target_batch_size=4096",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      CoalesceBatchesExec: target_batch_size=4096",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = expected_optimized_bounded;
+
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_best_effort_keeps_failing_subtree() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = stream_exec_ordered(&schema, sort_exprs);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let failing = PassThroughTestExec::new(repartition_hash)
+            .with_reject_new_children(true)
+            .into_arc();
+        let coalesce_partitions = coalesce_partitions_exec(failing);
+        let sort = sort_exec(vec![sort_expr("a", &schema)], coalesce_partitions, false);
+        let repartition_rr2 = repartition_exec_round_robin(sort);
+        let repartition_hash2 = repartition_exec_hash(repartition_rr2);
+        let filter = filter_exec(repartition_hash2);
+        let sort2 = sort_exec(vec![sort_expr("a", &schema)], filter, true);
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort2);
+
+        // By default, the failure is propagated:
+        let config = ConfigOptions::new();
+        assert!(optimize_with_config(physical_plan.clone(), &config).is_err());
+
+        // In best-effort mode, only the failing subtree is left unoptimized:
+        let mut config = ConfigOptions::new();
+        config.optimizer.order_preserving_best_effort = true;
+        let (optimized, decisions) =
+            ReplaceWithOrderPreservingVariants::new(false, false)
+                .optimize_and_record_decisions(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  FilterExec: c@1 > 3",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "          CoalescePartitionsExec",
+            "            PassThroughTestExec",
+            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                  StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        // The failure is recorded as the reason to keep the lower sort:
+        assert_eq!(decisions.len(), 2);
+        assert_eq!(decisions[0].path, [0, 0, 0, 0, 0]);
+        assert!(matches!(
+            &decisions[0].outcome,
+            SortOutcome::Kept(SortKeptReason::ConversionFailed { error })
+                if error.contains("PassThroughTestExec does not accept new children")
+        ));
+        assert_eq!(
+            decisions[1],
+            SortRemovalDecision {
+                path: vec![0],
+                outcome: SortOutcome::Removed(SortRemovalReason::OrderPreservingVariants),
+            }
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_sink_requiring_ordering(
+        #[values(false, true)] sink_requires_ordering: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let sort = sort_exec(sort_exprs.clone(), coalesce_partitions, false);
+        let sort_order = sink_requires_ordering
+            .then(|| PhysicalSortRequirement::from_sort_exprs(&sort_exprs));
+        let physical_plan = data_sink_exec(sort, sort_order);
+
+        let expected_input = [
+            "DataSinkExec: sink=TestSink",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "    CoalescePartitionsExec",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_sink_requires_ordering = [
+            "DataSinkExec: sink=TestSink",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let optimized = optimize_with_config(physical_plan, &ConfigOptions::new())?;
+        if sink_requires_ordering {
+            assert_eq!(
+                get_plan_string(&optimized),
+                expected_optimized_sink_requires_ordering
+            );
+        } else {
+            assert_eq!(get_plan_string(&optimized), expected_input);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_on_dictionary_sort_key() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "a",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                false,
+            ),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        // Dictionary keys are deliberately in the reverse order of the decoded
+        // values, so merging on keys would produce a wrong ordering:
+        let dictionary_batch = |values: Vec<&str>, keys: Vec<i32>| -> Result<_> {
+            let a = DictionaryArray::<Int32Type>::try_new(
+                Int32Array::from(keys.clone()),
+                Arc::new(StringArray::from(values)),
+            )?;
+            let b = Int32Array::from(keys);
+            Ok(RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(a), Arc::new(b)],
+            )?)
+        };
+        let partitions = vec![
+            vec![dictionary_batch(vec!["z", "m", "a"], vec![2, 1, 0])?],
+            vec![dictionary_batch(vec!["y", "b"], vec![1, 0])?],
+        ];
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = Arc::new(
+            MemoryExec::try_new(&partitions, schema.clone(), None)?
+                .with_sort_information(vec![sort_exprs.clone()]),
+        );
+        let coalesce_partitions = coalesce_partitions_exec(source);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+
+        let config = SessionConfig::new().with_prefer_existing_sort(true);
+        let optimized = optimize_with_config(physical_plan, config.options())?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  MemoryExec: partitions=2, partition_sizes=[1, 1], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        let task_ctx = SessionContext::new_with_config(config).task_ctx();
+        let batches = collect(optimized, task_ctx).await?;
+        let expected = [
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| a | 2 |",
+            "| b | 1 |",
+            "| m | 1 |",
+            "| y | 0 |",
+            "| z | 0 |",
+            "+---+---+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_not_removing_sort_that_merges_partitions() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        // The sort does not preserve partitioning, so it produces the single
+        // partition required by the limit above it:
+        let sort = sort_exec(sort_exprs, repartition_hash, false);
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            Arc::new(GlobalLimitExec::new(sort, 0, Some(10)));
+
+        let expected = [
+            "GlobalLimitExec: skip=0, fetch=10",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected);
+
+        // Even though a sort-preserving `RepartitionExec` satisfies the ordering
+        // within each partition, removing the sort would change the output
+        // partitioning. Hence, the sort is kept:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        assert_eq!(get_plan_string(&optimized), expected);
+        assert_eq!(optimized.output_partitioning().partition_count(), 1);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_introduced_merge_uses_configured_prefetch(
+        #[values(None, Some(4))] prefetch: Option<usize>,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition = repartition_exec_round_robin(source);
+        let coalesce_partitions = coalesce_partitions_exec(repartition);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        if let Some(prefetch) = prefetch {
+            config.execution.sort_preserving_merge_batch_prefetch = prefetch;
+        }
+        let optimized = optimize_with_config(physical_plan, &config)?;
+
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // By default, the introduced merge buffers a single batch ahead for each
+        // input partition, just like any other merge:
+        let spm = optimized
+            .as_any()
+            .downcast_ref::<SortPreservingMergeExec>()
+            .unwrap();
+        let default_prefetch =
+            SortPreservingMergeExec::new(vec![], optimized.clone()).prefetch();
+        assert_eq!(spm.prefetch(), prefetch.unwrap_or(default_prefetch));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reoptimize_with_more_permissive_config() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The first run does not prefer existing sorts, so the plan stays as is:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = false;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        assert_eq!(get_plan_string(&optimized), expected_input);
+
+        // Re-running with a more permissive config performs the conversion on
+        // the already optimized plan:
+        config.optimizer.prefer_existing_sort = true;
+        let reoptimized = optimize_with_config(optimized, &config)?;
+        let expected_reoptimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&reoptimized), expected_reoptimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reoptimize_with_less_permissive_config() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+
+        // The first run prefers existing sorts, so the conversion happens:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // Re-running with a less permissive config keeps the conversions, as
+        // there is no `SortExec` left to reintroduce:
+        config.optimizer.prefer_existing_sort = false;
+        let reoptimized = optimize_with_config(optimized, &config)?;
+        assert_eq!(get_plan_string(&reoptimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_cross_join_single_row_side(
+        #[values(1, 2)] left_rows: i32,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+
+        let left_schema =
+            Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        let left_batch = RecordBatch::try_new(
+            Arc::clone(&left_schema),
+            vec![Arc::new(Int32Array::from_iter_values(0..left_rows))],
+        )?;
+        let left = Arc::new(MemoryExec::try_new(&[vec![left_batch]], left_schema, None)?);
+        let cross_join: Arc<dyn ExecutionPlan> =
+            Arc::new(CrossJoinExec::new(left, repartition_hash));
+        let join_sort_exprs = vec![sort_expr("a", &cross_join.schema())];
+        let sort = sort_exec(join_sort_exprs.clone(), cross_join, true);
+        let physical_plan = sort_preserving_merge_exec(join_sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@1 ASC NULLS LAST]",
+            "  SortExec: expr=[a@1 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    CrossJoinExec",
+            "      MemoryExec: partitions=1, partition_sizes=[1]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+
+        // A single-row left side maintains the ordering of the right side, so
+        // the connection is kept through the cross join:
+        let expected_optimized = if left_rows == 1 {
+            vec![
+                "SortPreservingMergeExec: [a@1 ASC NULLS LAST]",
+                "  CrossJoinExec",
+                "    MemoryExec: partitions=1, partition_sizes=[1]",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_optimize_and_count_sorts() -> Result<()> {
+        // Same plan as in `test_with_lost_and_kept_ordering` (unbounded case):
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = stream_exec_ordered(&schema, sort_exprs);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let sort = sort_exec(
+            vec![sort_expr_default("c", &coalesce_partitions.schema())],
+            coalesce_partitions,
+            false,
+        );
+        let repartition_rr2 = repartition_exec_round_robin(sort);
+        let repartition_hash2 = repartition_exec_hash(repartition_rr2);
+        let filter = filter_exec(repartition_hash2);
+        let sort2 =
+            sort_exec(vec![sort_expr_default("c", &filter.schema())], filter, true);
+        let physical_plan = sort_preserving_merge_exec(
+            vec![sort_expr_default("c", &sort2.schema())],
+            sort2,
+        );
+
+        // The outer sort is removed, but the inner one is kept as its input is
+        // ordered by a different column:
+        let (optimized, before, after) =
+            ReplaceWithOrderPreservingVariants::new(false, false)
+                .optimize_and_count_sorts(physical_plan, &ConfigOptions::new())?;
+        assert_eq!((before, after), (2, 1));
+        let expected_optimized = [
+            "SortPreservingMergeExec: [c@1 ASC]",
+            "  FilterExec: c@1 > 3",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=c@1 ASC",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
+            "          CoalescePartitionsExec",
+            "            RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "              RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "                StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_sort_over_placeholder_row() -> Result<()> {
+        let schema = create_test_schema()?;
+        let placeholder = Arc::new(PlaceholderRowExec::new(Arc::clone(&schema)));
+        let repartition = repartition_exec_round_robin(placeholder);
+        let physical_plan = sort_exec(vec![sort_expr("a", &schema)], repartition, true);
+
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "  RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "    PlaceholderRowExec",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // A single row is trivially ordered, so the sort is removed without
+        // converting the `RepartitionExec`:
+        let optimized = optimize_with_config(physical_plan, &ConfigOptions::new())?;
+        let expected_optimized = [
+            "RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "  PlaceholderRowExec",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_sort_over_empty_relation() -> Result<()> {
+        let schema = create_test_schema()?;
+        let empty = Arc::new(EmptyExec::new(Arc::clone(&schema)));
+        let physical_plan = sort_exec(vec![sort_expr("a", &schema)], empty, false);
+
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  EmptyExec",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let optimized = optimize_with_config(physical_plan, &ConfigOptions::new())?;
+        assert_eq!(get_plan_string(&optimized), ["EmptyExec"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_not_replacing_with_volatile_sort_key() -> Result<()> {
+        let schema = create_test_schema()?;
+        let random_expr: Arc<dyn PhysicalExpr> = Arc::new(ScalarFunctionExpr::new(
+            "random",
+            crate::functions::math::random(),
+            vec![],
+            DataType::Float64,
+        ));
+        let sort_exprs = vec![PhysicalSortExpr {
+            expr: random_expr,
+            options: SortOptions::default(),
+        }];
+        // Even if the source claims to be ordered by `random()`, that ordering
+        // is not stable and can not be preserved:
+        let source = Arc::new(
+            MemoryExec::try_new(&[vec![]], Arc::clone(&schema), None)?
+                .with_sort_information(vec![sort_exprs.clone()]),
+        );
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected = [
+            "SortPreservingMergeExec: [random() ASC]",
+            "  SortExec: expr=[random() ASC], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@2], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        MemoryExec: partitions=1, partition_sizes=[0], output_ordering=random() ASC",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        assert_eq!(get_plan_string(&optimized), expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_parallelize_sort_over_coalesce(
+        #[values(false, true)] parallelize_sort: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![]);
+        let repartition = repartition_exec_round_robin(source);
+        let coalesce_partitions = coalesce_partitions_exec(repartition);
+        let physical_plan =
+            sort_exec(vec![sort_expr("a", &schema)], coalesce_partitions, false);
+
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.parallelize_sort = parallelize_sort;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = if parallelize_sort {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_not_replacing_on_hash_join_build_side() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let left_source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let left_repartition_rr = repartition_exec_round_robin(left_source);
+        let left_repartition_hash = repartition_exec_hash(left_repartition_rr);
+        let right_source = csv_exec_sorted(&schema, sort_exprs);
+        let right_repartition_rr = repartition_exec_round_robin(right_source);
+        let right_repartition_hash = repartition_exec_hash(right_repartition_rr);
+        let hash_join_exec =
+            hash_join_exec(left_repartition_hash, right_repartition_hash);
+
+        // Sort by the `a` column of the probe (right) side:
+        let join_sort_exprs = vec![PhysicalSortExpr {
+            expr: Arc::new(Column::new("a", 3)),
+            options: SortOptions {
+                descending: false,
+                nulls_first: false,
+            },
+        }];
+        let sort = sort_exec(join_sort_exprs.clone(), hash_join_exec, true);
+        let physical_plan = sort_preserving_merge_exec(join_sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@3 ASC NULLS LAST]",
+            "  SortExec: expr=[a@3 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // Only the `RepartitionExec` on the probe side is converted:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@3 ASC NULLS LAST]",
+            "  HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replace_sort_on_multiple_expressions() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source_sort_exprs = vec![
+            sort_expr("a", &schema),
+            sort_expr("c", &schema),
+            sort_expr("d", &schema),
+        ];
+        let source = csv_exec_sorted(&schema, source_sort_exprs);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        // The sort expressions of the sort are a prefix of the source ordering:
+        let sort_exprs = vec![
+            sort_expr("a", &repartition_hash.schema()),
+            sort_expr("c", &repartition_hash.schema()),
+        ];
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST,c@1 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST,c@1 ASC NULLS LAST,d@2 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST, c@1 ASC NULLS LAST, d@2 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_sink_requiring_per_partition_ordering() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let spm = sort_preserving_merge_exec(sort_exprs.clone(), sort);
+        let sort_order = PhysicalSortRequirement::from_sort_exprs(&sort_exprs);
+        let physical_plan = data_sink_exec(spm, Some(sort_order));
+
+        let expected_input = [
+            "DataSinkExec: sink=TestSink",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The per-partition sort is redundant once the `RepartitionExec`
+        // preserves the ordering of each partition:
+        let optimized = optimize_with_config(physical_plan, &ConfigOptions::new())?;
+        let expected_optimized = [
+            "DataSinkExec: sink=TestSink",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lint_order_preserving() -> Result<()> {
+        // Same plan as in `test_replace_multiple_input_repartition_1` (bounded case):
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs);
+        let repartition = repartition_exec_hash(repartition_exec_round_robin(source));
+        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition, true);
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        // The sort is removable only with `prefer_existing_sort`:
+        let lints = ReplaceWithOrderPreservingVariants::new(false, false)
+            .lint_order_preserving(&physical_plan, &ConfigOptions::new())?;
+        assert_eq!(
+            lints,
+            vec![Lint {
+                sort: "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]"
+                    .to_string(),
+                severity: LintSeverity::Warning,
+                message: "sort could be removed by using order-preserving variants \
+                          of operators below it"
+                    .to_string(),
+                suggestion: Some(
+                    "enable datafusion.optimizer.prefer_existing_sort to remove this sort"
+                        .to_string()
+                ),
+            }]
+        );
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let lints = ReplaceWithOrderPreservingVariants::new(false, false)
+            .lint_order_preserving(&physical_plan, &config)?;
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].severity, LintSeverity::Info);
+        assert_eq!(lints[0].suggestion, None);
+
+        // Linting does not change the plan:
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_shared_repartition() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        // The same `RepartitionExec` feeds an order-insensitive aggregate and
+        // a sort:
+        let shared_repartition = repartition_exec_hash(repartition_rr);
+        let group_by = PhysicalGroupBy::new_single(vec![(
+            col("c", &shared_repartition.schema())?,
+            "c".to_string(),
+        )]);
+        let aggregate: Arc<dyn ExecutionPlan> = Arc::new(AggregateExec::try_new(
+            AggregateMode::SinglePartitioned,
+            group_by,
+            vec![],
+            vec![],
+            Arc::clone(&shared_repartition),
+            shared_repartition.schema(),
+        )?);
+        let sort = sort_exec(sort_exprs, Arc::clone(&shared_repartition), true);
+        let physical_plan = hash_join_exec(Arc::clone(&aggregate), sort);
+
+        let expected_input = [
+            "HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@0, c@1)]",
+            "  AggregateExec: mode=SinglePartitioned, gby=[c@1 as c], aggr=[]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // Only the copy of the `RepartitionExec` below the sort is converted:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@0, c@1)]",
+            "  AggregateExec: mode=SinglePartitioned, gby=[c@1 as c], aggr=[]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        let expected_plan = hash_join_exec(
+            Arc::clone(&aggregate),
+            Arc::new(
+                RepartitionExec::try_new(
+                    Arc::clone(shared_repartition.children()[0]),
+                    shared_repartition.output_partitioning().clone(),
+                )?
+                .with_preserve_order(),
+            ),
+        );
+        assert!(plans_structurally_equal(&optimized, &expected_plan));
+        // The order-preserving variant is structurally different from the
+        // original repartition, as it has an output ordering:
+        assert!(!plans_structurally_equal(
+            optimized.children()[1],
+            &shared_repartition
+        ));
+
+        // The aggregate still consumes the original `RepartitionExec`:
+        let optimized_aggregate = optimized.children()[0];
+        assert!(Arc::ptr_eq(
+            optimized_aggregate.children()[0],
+            &shared_repartition
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_key_after_renaming_projection() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![sort_expr("a", &schema)]);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        // Swap the columns `a` and `c`, rename `a` to `a2` and drop `d`:
+        let projection_exprs = vec![
+            (Arc::new(Column::new("c", 1)) as _, "c".to_string()),
+            (Arc::new(Column::new("a", 0)) as _, "a2".to_string()),
+        ];
+        let projection: Arc<dyn ExecutionPlan> =
+            Arc::new(ProjectionExec::try_new(projection_exprs, repartition_hash)?);
+        let coalesce_partitions = coalesce_partitions_exec(projection);
+        let physical_plan = sort_exec(
+            vec![sort_expr("a2", &coalesce_partitions.schema())],
+            coalesce_partitions,
+            false,
+        );
+
+        let expected_input = [
+            "SortExec: expr=[a2@1 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    ProjectionExec: expr=[c@1 as c, a@0 as a2]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The repartition preserves the ordering on the source column `a@0`,
+        // while the merge key refers to the projected column `a2@1`:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a2@1 ASC NULLS LAST]",
+            "  ProjectionExec: expr=[c@1 as c, a@0 as a2]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert!(is_repartition(optimized.children()[0].children()[0]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replace_repartition_retains_exact_partitioning() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = repartition_exec_round_robin(csv_exec_sorted(&schema, sort_exprs));
+        let source_schema = source.schema();
+        let partitionings = [
+            Partitioning::RoundRobinBatch(5),
+            Partitioning::Hash(
+                vec![col("c", &source_schema)?, col("a", &source_schema)?],
+                3,
+            ),
+            Partitioning::UnknownPartitioning(4),
+        ];
+
+        for partitioning in partitionings {
+            let repartition = Arc::new(RepartitionExec::try_new(
+                Arc::clone(&source),
+                partitioning.clone(),
+            )?);
+            let coalesce_partitions = coalesce_partitions_exec(repartition);
+            let physical_plan = sort_exec(
+                vec![sort_expr("a", &coalesce_partitions.schema())],
+                coalesce_partitions,
+                false,
+            );
+
+            let mut config = ConfigOptions::new();
+            config.optimizer.prefer_existing_sort = true;
+            config.optimizer.allow_order_preserving_round_robin = true;
+            let optimized = optimize_with_config(physical_plan, &config)?;
+            assert!(is_sort_preserving_merge(&optimized));
+            let repartition = optimized.children()[0]
+                .as_any()
+                .downcast_ref::<RepartitionExec>()
+                .expect("RepartitionExec");
+            assert!(repartition.preserve_order());
+            // `Partitioning::UnknownPartitioning` never compares equal, so
+            // compare the displayed form instead:
+            assert_eq!(
+                repartition.partitioning().to_string(),
+                partitioning.to_string()
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_sort_below_ordered_aggregate() -> Result<()> {
+        let schema = create_test_schema()?;
+        // The source is ordered by `c` and then by `a` within each `c` group:
+        let c = Arc::new(Column::new("c", 1)) as Arc<dyn PhysicalExpr>;
+        let a = Arc::new(Column::new("a", 0)) as Arc<dyn PhysicalExpr>;
+        let options = SortOptions {
+            descending: false,
+            nulls_first: false,
+        };
+        let source_ordering = vec![
+            PhysicalSortExpr::new(Arc::clone(&c), options),
+            PhysicalSortExpr::new(Arc::clone(&a), options),
+        ];
+        let source = csv_exec_sorted(&schema, source_ordering.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        // The sort delivers the within-group ordering of `array_agg(d ORDER BY a)`:
+        let sort = sort_exec(source_ordering, repartition_hash, true);
+        let sort_schema = sort.schema();
+        let array_agg =
+            AggregateExprBuilder::new(array_agg_udaf(), vec![col("d", &sort_schema)?])
+                .order_by(vec![PhysicalSortExpr::new(Arc::clone(&a), options)])
+                .schema(Arc::clone(&sort_schema))
+                .alias("array_agg(d) ORDER BY [a ASC NULLS LAST]")
+                .build()?;
+        let group_by = PhysicalGroupBy::new_single(vec![(c, "c".to_string())]);
+        let physical_plan: Arc<dyn ExecutionPlan> = Arc::new(AggregateExec::try_new(
+            AggregateMode::SinglePartitioned,
+            group_by,
+            vec![array_agg],
+            vec![None],
+            sort,
+            sort_schema,
+        )?);
+
+        let expected_input = [
+            "AggregateExec: mode=SinglePartitioned, gby=[c@1 as c], aggr=[array_agg(d) ORDER BY [a ASC NULLS LAST]], ordering_mode=Sorted",
+            "  SortExec: expr=[c@1 ASC NULLS LAST,a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[c@1 ASC NULLS LAST, a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "AggregateExec: mode=SinglePartitioned, gby=[c@1 as c], aggr=[array_agg(d) ORDER BY [a ASC NULLS LAST]], ordering_mode=Sorted",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=c@1 ASC NULLS LAST,a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[c@1 ASC NULLS LAST, a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_two_level_repartition_chain(
+        #[values(false, true)] lower_convertible: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let lower_repartition = repartition_exec_hash(repartition_rr);
+        // When the lower part of the chain can not be converted, the ordering
+        // is already lost below the upper `RepartitionExec`:
+        let lower = if lower_convertible {
+            lower_repartition
+        } else {
+            PassThroughTestExec::new(lower_repartition)
+                .with_reject_new_children(true)
+                .into_arc()
+        };
+        let upper_repartition = repartition_exec_hash(lower);
+        let sort = sort_exec(sort_exprs, upper_repartition, true);
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.order_preserving_best_effort = true;
+        let optimized = optimize_with_config(Arc::clone(&physical_plan), &config)?;
+        if lower_convertible {
+            // Both repartitions need to preserve order to remove the sort:
+            let expected_optimized = [
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ];
+            assert_eq!(get_plan_string(&optimized), expected_optimized);
+        } else {
+            // Converting only the upper repartition would not deliver the
+            // ordering, so nothing is converted and the sort is kept:
+            let expected_optimized = [
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+                "      PassThroughTestExec",
+                "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+                "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ];
+            assert_eq!(get_plan_string(&optimized), expected_optimized);
+            assert!(plans_structurally_equal(&optimized, &physical_plan));
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_allow_order_preserving_round_robin(
+        #[values(false, true)] allow_order_preserving_round_robin: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        // This round-robin repartition has multiple input partitions, so it
+        // does not maintain the ordering of its input:
+        let repartition_rr2 = repartition_exec_round_robin(repartition_rr);
+        let sort = sort_exec(sort_exprs, repartition_rr2, true);
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.allow_order_preserving_round_robin =
+            allow_order_preserving_round_robin;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = if allow_order_preserving_round_robin {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_sort_over_upstream_top_k() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![]);
+        let top_k = Arc::new(
+            SortExec::new(vec![sort_expr("a", &schema)], source).with_fetch(Some(1000)),
+        );
+        let repartition_rr = repartition_exec_round_robin(top_k);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition_hash, true);
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        SortExec: TopK(fetch=1000), expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The ordering produced by the `TopK` sort only needs to be preserved
+        // through the hash repartition for the full sort to become redundant:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      SortExec: TopK(fetch=1000), expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimal_ordering() -> Result<()> {
+        let schema = create_test_schema()?;
+        let col_a = col("a", &schema)?;
+        let col_b = col("b", &schema)?;
+        let col_c = col("c", &schema)?;
+        let col_d = col("d", &schema)?;
+        let mut eq_properties = EquivalenceProperties::new(Arc::clone(&schema))
+            .add_constants(vec![
+                ConstExpr::from(&col_b).with_across_partitions(true),
+                ConstExpr::from(&col_d),
+            ]);
+        eq_properties.add_equal_conditions(&col_a, &col_c)?;
+
+        // `b` is constant across partitions and `c` is equal to the earlier
+        // `a`, so both are dropped. `d` is only constant within partitions, so
+        // it is kept:
+        let exprs = vec![
+            sort_expr("a", &schema),
+            sort_expr("b", &schema),
+            sort_expr("c", &schema),
+            sort_expr("d", &schema),
+        ];
+        assert_eq!(
+            minimal_ordering(&exprs, &eq_properties),
+            vec![sort_expr("a", &schema), sort_expr("d", &schema)]
+        );
+
+        // A key that is entirely constant is left as is:
+        let exprs = vec![sort_expr("b", &schema)];
+        assert_eq!(minimal_ordering(&exprs, &eq_properties), exprs);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_statistics_unchanged_after_removing_sort() -> Result<()> {
+        let schema = create_test_schema()?;
+        let columns = (0..4)
+            .map(|_| Arc::new(Int32Array::from_iter_values(0..1000)) as _)
+            .collect();
+        let batch = RecordBatch::try_new(Arc::clone(&schema), columns)?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = Arc::new(
+            MemoryExec::try_new(&[vec![batch]], Arc::clone(&schema), None)?
+                .with_sort_information(vec![sort_exprs.clone()]),
+        );
+        let repartition = repartition_exec_hash(repartition_exec_round_robin(source));
+        let sort = sort_exec(sort_exprs.clone(), repartition, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(Arc::clone(&physical_plan), &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@2], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      MemoryExec: partitions=1, partition_sizes=[1], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert_eq!(optimized.statistics()?.num_rows, Precision::Exact(1000));
+        assert_eq!(
+            optimized.statistics()?.num_rows,
+            physical_plan.statistics()?.num_rows
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_factory_replaces_surviving_sorts() -> Result<()> {
+        let schema = create_test_schema()?;
+        // The build side sort can not be removed, as its source is unordered:
+        let unordered_source = csv_exec_sorted(&schema, vec![]);
+        let left = sort_exec(
+            vec![sort_expr("a", &schema)],
+            repartition_exec_hash(repartition_exec_round_robin(unordered_source)),
+            true,
+        );
+        // The probe side sort can be removed by preserving the source ordering:
+        let ordered_source = csv_exec_sorted(&schema, vec![sort_expr("a", &schema)]);
+        let right = sort_exec(
+            vec![sort_expr("a", &schema)],
+            repartition_exec_hash(repartition_exec_round_robin(ordered_source)),
+            true,
+        );
+        let physical_plan = hash_join_exec(left, right);
+
+        // Marks the surviving sorts by wrapping them into a distinctive
+        // `CoalesceBatchesExec`:
+        let sort_factory = |sort: &SortExec| -> Arc<dyn ExecutionPlan> {
+            let sort = SortExec::new(sort.expr().to_vec(), Arc::clone(sort.input()))
+                .with_preserve_partitioning(sort.preserve_partitioning())
+                .with_fetch(sort.fetch());
+            Arc::new(CoalesceBatchesExec::new(Arc::new(sort), 4242))
+        };
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = ReplaceWithOrderPreservingVariants::new(false, false)
+            .optimize_with_sort_factory(physical_plan, &config, &sort_factory)?;
+        let expected_optimized = [
+            "HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "  CoalesceBatchesExec: target_batch_size=4242",
+            "    SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_sql_round_trip(
+        #[values(false, true)] prefer_existing_sort: bool,
+    ) -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let partitions = (0..3)
+            .map(|i| {
+                let batch = RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![
+                        Arc::new(Int32Array::from(vec![i, i + 3, i + 6])),
+                        Arc::new(Int32Array::from(vec![1, 2, 3])),
+                    ],
+                )?;
+                Ok(vec![batch])
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let table = MemTable::try_new(Arc::clone(&schema), partitions)?
+            .with_sort_order(vec![vec![datafusion_expr::col("a").sort(true, false)]]);
+        let config = SessionConfig::new()
+            .with_target_partitions(8)
+            .with_prefer_existing_sort(prefer_existing_sort);
+        let ctx = SessionContext::new_with_config(config);
+        ctx.register_table("t", Arc::new(table))?;
+        let physical_plan = ctx
+            .sql("SELECT a, SUM(b) FROM t GROUP BY a ORDER BY a")
+            .await?
+            .create_physical_plan()
+            .await?;
+
+        // The full planner already runs this sub-rule as part of sort
+        // enforcement, so the sort below the final aggregation is only removed
+        // when existing orderings are preferred:
+        let expected_plan = if prefer_existing_sort {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  AggregateExec: mode=FinalPartitioned, gby=[a@0 as a], aggr=[sum(t.b)], ordering_mode=Sorted",
+                "    CoalesceBatchesExec: target_batch_size=8192",
+                "      RepartitionExec: partitioning=Hash([a@0], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=3, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "          AggregateExec: mode=Partial, gby=[a@0 as a], aggr=[sum(t.b)], ordering_mode=Sorted",
+                "            MemoryExec: partitions=3, partition_sizes=[1, 1, 1], output_ordering=a@0 ASC NULLS LAST",
+            ]
+        } else {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  AggregateExec: mode=FinalPartitioned, gby=[a@0 as a], aggr=[sum(t.b)], ordering_mode=Sorted",
+                "    SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+                "      CoalesceBatchesExec: target_batch_size=8192",
+                "        RepartitionExec: partitioning=Hash([a@0], 8), input_partitions=8",
+                "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=3",
+                "            AggregateExec: mode=Partial, gby=[a@0 as a], aggr=[sum(t.b)], ordering_mode=Sorted",
+                "              MemoryExec: partitions=3, partition_sizes=[1, 1, 1], output_ordering=a@0 ASC NULLS LAST",
+            ]
+        };
+        assert_eq!(get_plan_string(&physical_plan), expected_plan);
+
+        // Applying the sub-rule once more does not change the planned result:
+        let optimized = optimize_with_config(
+            Arc::clone(&physical_plan),
+            ctx.copied_config().options(),
+        )?;
+        assert_eq!(get_plan_string(&optimized), expected_plan);
+
+        let batches = collect(optimized, ctx.task_ctx()).await?;
+        let expected = [
+            "+---+----------+",
+            "| a | sum(t.b) |",
+            "+---+----------+",
+            "| 0 | 1        |",
+            "| 1 | 1        |",
+            "| 2 | 1        |",
+            "| 3 | 2        |",
+            "| 4 | 2        |",
+            "| 5 | 2        |",
+            "| 6 | 3        |",
+            "| 7 | 3        |",
+            "| 8 | 3        |",
+            "+---+----------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hash_key_differs_from_merge_key() -> Result<()> {
+        let schema = create_test_schema()?;
+        let options = SortOptions {
+            descending: false,
+            nulls_first: false,
+        };
+        // The source ordering `[a, d]` covers the required `[a]` ordering,
+        // while the repartition routes rows by the unrelated column `c`:
+        let source_ordering = vec![
+            PhysicalSortExpr::new(Arc::new(Column::new("a", 0)), options),
+            PhysicalSortExpr::new(Arc::new(Column::new("d", 2)), options),
+        ];
+        let source = csv_exec_sorted(&schema, source_ordering);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition_hash, true);
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST,d@2 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST, d@2 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // Rows are still routed by `c`, but merged by the input ordering:
+        let repartition = optimized.children()[0]
+            .as_any()
+            .downcast_ref::<RepartitionExec>()
+            .expect("RepartitionExec");
+        let Partitioning::Hash(hash_exprs, _) = repartition.partitioning() else {
+            unreachable!("expected hash partitioning");
+        };
+        assert_eq!(hash_exprs[0].to_string(), "c@1");
+        assert!(optimized.children()[0]
+            .equivalence_properties()
+            .ordering_satisfy(&[sort_expr("a", &schema)]));
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_coalesce_over_heterogeneous_partition_orderings(
+        #[values(false, true)] compatible_orderings: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let options = SortOptions {
+            descending: false,
+            nulls_first: false,
+        };
+        let col_a = Arc::new(Column::new("a", 0)) as Arc<dyn PhysicalExpr>;
+        let col_d = Arc::new(Column::new("d", 2)) as Arc<dyn PhysicalExpr>;
+        // The partitions of the union are ordered by `[a, d]` and either by
+        // `[a]` (compatible) or by `[d]` (incompatible):
+        let first = csv_exec_sorted(
+            &schema,
+            vec![
+                PhysicalSortExpr::new(Arc::clone(&col_a), options),
+                PhysicalSortExpr::new(Arc::clone(&col_d), options),
+            ],
+        );
+        let second_ordering = if compatible_orderings { col_a } else { col_d };
+        let second = csv_exec_sorted(
+            &schema,
+            vec![PhysicalSortExpr::new(second_ordering, options)],
+        );
+        let union = Arc::new(UnionExec::new(vec![first, second]));
+        let coalesce_partitions = coalesce_partitions_exec(union);
+        let physical_plan =
+            sort_exec(vec![sort_expr("a", &schema)], coalesce_partitions, false);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(Arc::clone(&physical_plan), &config)?;
+        if compatible_orderings {
+            // The merge key is the common ordering of all partitions:
+            let expected_optimized = [
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  UnionExec",
+                "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST, d@2 ASC NULLS LAST], has_header=true",
+                "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ];
+            assert_eq!(get_plan_string(&optimized), expected_optimized);
+        } else {
+            // There is no common ordering, so the sort is kept:
+            assert_eq!(get_plan_string(&optimized), get_plan_string(&physical_plan));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_sort_below_range_window_frame() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let spm = sort_preserving_merge_exec(sort_exprs.clone(), sort);
+        // The `RANGE` frame of the window needs its input ordered by `a` to
+        // determine the peers of each row:
+        let physical_plan = bounded_window_exec("a", sort_exprs, spm);
+
+        let expected_input = [
+            "BoundedWindowAggExec: wdw=[count: Ok(Field { name: \"count\", data_type: Int64, nullable: false, dict_id: 0, dict_is_ordered: false, metadata: {} }), frame: WindowFrame { units: Range, start_bound: Preceding(NULL), end_bound: CurrentRow, is_causal: false }], mode=[Sorted]",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "BoundedWindowAggExec: wdw=[count: Ok(Field { name: \"count\", data_type: Int64, nullable: false, dict_id: 0, dict_is_ordered: false, metadata: {} }), frame: WindowFrame { units: Range, start_bound: Preceding(NULL), end_bound: CurrentRow, is_causal: false }], mode=[Sorted]",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cached_subtrees_are_skipped() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        // The build side is left unchanged by the sub-rule:
+        let children_calls = Arc::new(AtomicUsize::new(0));
+        let counting = PassThroughTestExec::new(csv_exec_sorted(&schema, vec![]))
+            .with_children_calls(Arc::clone(&children_calls))
+            .into_arc();
+        let left = coalesce_batches_exec(counting);
+        // The sort on the probe side is removed:
+        let right = sort_exec(
+            sort_exprs.clone(),
+            repartition_exec_hash(repartition_exec_round_robin(csv_exec_sorted(
+                &schema, sort_exprs,
+            ))),
+            true,
+        );
+        let physical_plan = hash_join_exec(left, right);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let mut cache = UnchangedSubtreeCache::new();
+        let optimized = ReplaceWithOrderPreservingVariants::new(false, false)
+            .optimize_with_observer(Arc::clone(&physical_plan), &config, &mut cache)?;
+        let expected_optimized = [
+            "HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "  CoalesceBatchesExec: target_batch_size=8192",
+            "    PassThroughTestExec",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert!(!cache.is_empty());
+        // Rendering the plan accesses the children as well:
+        let first_run_calls = children_calls.load(Ordering::Relaxed);
+        assert!(first_run_calls > 1);
+
+        // On the second run, the unchanged build side is not visited again,
+        // but the result is the same:
+        let optimized = ReplaceWithOrderPreservingVariants::new(false, false)
+            .optimize_with_observer(Arc::clone(&physical_plan), &config, &mut cache)?;
+        assert_eq!(children_calls.load(Ordering::Relaxed), first_run_calls);
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cached_subtrees_are_dropped_on_config_change() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let physical_plan = sort_preserving_merge_exec(
+            sort_exprs.clone(),
+            sort_exec(
+                sort_exprs.clone(),
+                repartition_exec_hash(repartition_exec_round_robin(csv_exec_sorted(
+                    &schema, sort_exprs,
+                ))),
+                true,
+            ),
+        );
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false);
+
+        // Without `prefer_existing_sort`, the plan is left unchanged and cached:
+        let mut config = ConfigOptions::new();
+        let mut cache = UnchangedSubtreeCache::new();
+        let optimized =
+            rule.optimize_with_observer(Arc::clone(&physical_plan), &config, &mut cache)?;
+        assert!(Arc::ptr_eq(&optimized, &physical_plan));
+        assert!(!cache.is_empty());
+
+        // Once the option is set, the cached result is not reused anymore:
+        config.optimizer.prefer_existing_sort = true;
+        let optimized =
+            rule.optimize_with_observer(Arc::clone(&physical_plan), &config, &mut cache)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // Neither is it with a different rule:
+        config.optimizer.prefer_existing_sort = false;
+        let optimized =
+            rule.optimize_with_observer(Arc::clone(&physical_plan), &config, &mut cache)?;
+        assert!(Arc::ptr_eq(&optimized, &physical_plan));
+        let optimized = ReplaceWithOrderPreservingVariants::new(true, true)
+            .optimize_with_observer(Arc::clone(&physical_plan), &config, &mut cache)?;
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replace_sort_with_fetch_and_offset() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        // `ORDER BY a LIMIT 10 OFFSET 5`; the fetch of the sort also covers
+        // the skipped rows:
+        let sort =
+            Arc::new(SortExec::new(sort_exprs, coalesce_partitions).with_fetch(Some(15)));
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            Arc::new(GlobalLimitExec::new(sort, 5, Some(10)));
+
+        let expected_input = [
+            "GlobalLimitExec: skip=5, fetch=10",
+            "  SortExec: TopK(fetch=15), expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "    CoalescePartitionsExec",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The merge applies the fetch of the removed sort after the global
+        // ordering is established, and the limit above skips the offset:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "GlobalLimitExec: skip=5, fetch=10",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=15",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_unnest_between_sort_and_repartition() -> Result<()> {
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("c", DataType::Int32, false),
+            Field::new_list("l", Field::new_list_field(DataType::Int32, true), true),
+        ]));
+        let source = Arc::new(
+            MemoryExec::try_new(&[vec![]], Arc::clone(&input_schema), None)?
+                .with_sort_information(vec![vec![sort_expr("a", &input_schema)]]),
+        );
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        // Only the list column `l` is unnested, and the sort is on `a`:
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("c", DataType::Int32, false),
+            Field::new("l", DataType::Int32, true),
+        ]));
+        let unnest = Arc::new(UnnestExec::new(
+            repartition_hash,
+            vec![2],
+            vec![],
+            Arc::clone(&schema),
+            UnnestOptions::default(),
+        ));
+        let sort = sort_exec(vec![sort_expr("a", &schema)], unnest, true);
+        let physical_plan =
+            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    UnnestExec",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          MemoryExec: partitions=1, partition_sizes=[0], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  UnnestExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        MemoryExec: partitions=1, partition_sizes=[0], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_sorts_around_repartition() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let lower_sort = sort_exec(sort_exprs.clone(), repartition_rr, true);
+        let repartition_hash = repartition_exec_hash(lower_sort);
+        let upper_sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, upper_sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // Preserving the order in the repartition makes the upper sort
+        // redundant, and the ordered source makes the lower sort redundant:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replace_repartition_hashing_on_sort_key() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = Arc::new(RepartitionExec::try_new(
+            repartition_rr,
+            Partitioning::Hash(vec![col("a", &schema)?], 8),
+        )?);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        // Hashing on the sort key does not order the rows within an output
+        // partition, so the repartition still merges its inputs:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([a@0], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_optimize_with_deadline() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let mut physical_plan = csv_exec_sorted(&schema, sort_exprs.clone());
+        for _ in 0..100 {
+            let repartition_rr = repartition_exec_round_robin(physical_plan);
+            let repartition_hash = repartition_exec_hash(repartition_rr);
+            let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+            physical_plan = sort_preserving_merge_exec(sort_exprs.clone(), sort);
+        }
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+
+        // With an already passed deadline, the plan is returned unoptimized:
+        let optimized = ReplaceWithOrderPreservingVariants::new(false, false)
+            .optimize_with_deadline(
+                Arc::clone(&physical_plan),
+                &config,
+                Instant::now(),
+            )?;
+        assert_eq!(get_plan_string(&optimized), get_plan_string(&physical_plan));
+
+        // With enough time, all the sorts are removed:
+        let deadline = Instant::now() + std::time::Duration::from_secs(3600);
+        let optimized = ReplaceWithOrderPreservingVariants::new(false, false)
+            .optimize_with_deadline(physical_plan, &config, deadline)?;
+        assert!(!get_plan_string(&optimized)
+            .iter()
+            .any(|line| line.contains("SortExec")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_sort_on_window_function_output() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![sort_expr("a", &schema)]);
+        let source_schema = source.schema();
+        // `row_number() OVER (ORDER BY a)`:
+        let row_number = create_window_expr(
+            &WindowFunctionDefinition::WindowUDF(row_number_udwf()),
+            "row_number".to_string(),
+            &[],
+            &[],
+            &[sort_expr("a", &source_schema)],
+            Arc::new(WindowFrame::new(Some(false))),
+            source_schema.as_ref(),
+            false,
+        )?;
+        let window = Arc::new(BoundedWindowAggExec::try_new(
+            vec![row_number],
+            source,
+            vec![],
+            InputOrderMode::Sorted,
+        )?);
+        let repartition_rr = repartition_exec_round_robin(window);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort_exprs = vec![sort_expr("row_number", &repartition_hash.schema())];
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [row_number@3 ASC NULLS LAST]",
+            "  SortExec: expr=[row_number@3 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        BoundedWindowAggExec: wdw=[row_number: Ok(Field { name: \"row_number\", data_type: UInt64, nullable: false, dict_id: 0, dict_is_ordered: false, metadata: {} }), frame: WindowFrame { units: Range, start_bound: Preceding(NULL), end_bound: CurrentRow, is_causal: false }], mode=[Sorted]",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The window output is ordered by the row number, so preserving that
+        // ordering through the hash repartition makes the sort redundant:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [row_number@3 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST,row_number@3 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      BoundedWindowAggExec: wdw=[row_number: Ok(Field { name: \"row_number\", data_type: UInt64, nullable: false, dict_id: 0, dict_is_ordered: false, metadata: {} }), frame: WindowFrame { units: Range, start_bound: Preceding(NULL), end_bound: CurrentRow, is_causal: false }], mode=[Sorted]",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_root_coalesce_conversion_keeps_single_partition() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+        assert_eq!(physical_plan.output_partitioning().partition_count(), 1);
+
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The root coalesce is replaced by a merge, which still produces the
+        // single output partition the result stream relies on:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert_eq!(optimized.output_partitioning().partition_count(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_origins_with_sort_merge_join() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let join_input = || {
+            let source = csv_exec_sorted(&schema, sort_exprs.clone());
+            let repartition_rr = repartition_exec_round_robin(source);
+            let repartition_hash = repartition_exec_hash(repartition_rr);
+            sort_exec(sort_exprs.clone(), repartition_hash, true)
+        };
+        let join_on = vec![(
+            Arc::new(Column::new("a", 0)) as _,
+            Arc::new(Column::new("a", 0)) as _,
+        )];
+        let join =
+            sort_merge_join_exec(join_input(), join_input(), &join_on, &JoinType::Inner);
+        let sort_exprs = vec![sort_expr("d", &join.schema())];
+        let sort = sort_exec(sort_exprs.clone(), join, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [d@2 ASC NULLS LAST]",
+            "  SortExec: expr=[d@2 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    SortMergeJoin: join_type=Inner, on=[(a@0, a@0)]",
+            "      SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The sorts required by the join can be removed, but the sort of the
+        // result can not be:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let (optimized, before, after) =
+            ReplaceWithOrderPreservingVariants::new(false, false)
+                .optimize_and_count_sorts_by_origin(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [d@2 ASC NULLS LAST]",
+            "  SortExec: expr=[d@2 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    SortMergeJoin: join_type=Inner, on=[(a@0, a@0)]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert_eq!(
+            before,
+            SortOriginCounts {
+                order_by: 1,
+                operator: 2
+            }
+        );
+        assert_eq!(
+            after,
+            SortOriginCounts {
+                order_by: 1,
+                operator: 0
+            }
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_prefer_conversion_over_expensive_rescan_source(
+        #[values(false, true)] rescan_expensive: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The query is bounded and `prefer_existing_sort` is not set, so the
+        // sort is only removed if the source is expensive to re-scan:
+        let mut rule = ReplaceWithOrderPreservingVariants::new(false, false);
+        if rescan_expensive {
+            rule = rule.with_hints(Arc::new(ExpensiveCsvRescanHints));
+        }
+        let optimized = rule.optimize(physical_plan, &ConfigOptions::new())?;
+        let expected_optimized = if rescan_expensive {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_emit_distinct_preserve_order_type(
+        #[values(false, true)] emit_distinct_type: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.emit_distinct_preserve_order_type = emit_distinct_type;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = if emit_distinct_type {
+            [
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  SortPreservingRepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            [
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        // The distinct name does not change the operator type, so the rest of
+        // the optimizer still recognizes it as an order-preserving repartition:
+        let repartition = optimized.children()[0]
+            .as_any()
+            .downcast_ref::<RepartitionExec>()
+            .unwrap();
+        assert!(repartition.preserve_order());
+        assert!(is_repartition(optimized.children()[0]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_merge_join_with_one_unordered_side() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let join_input = |source_ordering| {
+            let source = csv_exec_sorted(&schema, source_ordering);
+            let repartition_rr = repartition_exec_round_robin(source);
+            let repartition_hash = repartition_exec_hash(repartition_rr);
+            sort_exec(sort_exprs.clone(), repartition_hash, true)
+        };
+        let join_on = vec![(
+            Arc::new(Column::new("a", 0)) as _,
+            Arc::new(Column::new("a", 0)) as _,
+        )];
+        let physical_plan = sort_merge_join_exec(
+            join_input(sort_exprs.clone()),
+            join_input(vec![]),
+            &join_on,
+            &JoinType::Inner,
+        );
+
+        let expected_input = [
+            "SortMergeJoin: join_type=Inner, on=[(a@0, a@0)]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // Each side of the join is handled on its own: The repartition of the
+        // ordered side is converted, which makes its sort redundant, while the
+        // unordered side keeps its sort to satisfy the join's requirement:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(Arc::clone(&physical_plan), &config)?;
+        let expected_optimized = [
+            "SortMergeJoin: join_type=Inner, on=[(a@0, a@0)]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        for (child, required) in optimized
+            .children()
+            .into_iter()
+            .zip(optimized.required_input_ordering())
+        {
+            let required = required.unwrap();
+            assert!(child
+                .equivalence_properties()
+                .ordering_satisfy_requirement(&required));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_on_equal_column_with_different_index() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![sort_expr("a", &schema)]);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        // Widen the schema so that `a` is referenced at both index 0 and 2:
+        let projection_exprs = vec![
+            (Arc::new(Column::new("a", 0)) as _, "a".to_string()),
+            (Arc::new(Column::new("c", 1)) as _, "c".to_string()),
+            (Arc::new(Column::new("a", 0)) as _, "a".to_string()),
+        ];
+        let projection: Arc<dyn ExecutionPlan> =
+            Arc::new(ProjectionExec::try_new(projection_exprs, repartition_hash)?);
+        let a_0 = Arc::new(Column::new("a", 0)) as Arc<dyn PhysicalExpr>;
+        let a_2 = Arc::new(Column::new("a", 2)) as Arc<dyn PhysicalExpr>;
+        let eq_group = projection.equivalence_properties().eq_group();
+        assert!(eq_group
+            .normalize_expr(Arc::clone(&a_2))
+            .eq(&eq_group.normalize_expr(Arc::clone(&a_0))));
+
+        let sort_exprs = vec![PhysicalSortExpr {
+            expr: a_2,
+            options: SortOptions {
+                descending: false,
+                nulls_first: false,
+            },
+        }];
+        let sort = sort_exec(sort_exprs.clone(), projection, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@2 ASC NULLS LAST]",
+            "  SortExec: expr=[a@2 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    ProjectionExec: expr=[a@0 as a, c@1 as c, a@0 as a]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The source is ordered by `a@0`, which is equal to `a@2` after the
+        // projection, so the sort on `a@2` is satisfied by preserving it:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@2 ASC NULLS LAST]",
+            "  ProjectionExec: expr=[a@0 as a, c@1 as c, a@0 as a]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_order_preserving_parallelism_bias(
+        #[values(0.0, 1.0)] bias: f64,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // With no bias the merge replaces the per-partition sorts, with full
+        // bias the parallel sorts are kept:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.order_preserving_parallelism_bias = bias;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = if bias == 0.0 {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_decorrelated_subquery_subtree() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        // The outer query:
+        let outer_source = csv_exec_sorted(&schema, vec![]);
+        let outer_repartition_rr = repartition_exec_round_robin(outer_source);
+        let outer_repartition_hash = repartition_exec_hash(outer_repartition_rr);
+        // The subquery, whose ordered output feeds the outer query:
+        let subquery_source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let subquery_repartition_rr = repartition_exec_round_robin(subquery_source);
+        let subquery_repartition_hash = repartition_exec_hash(subquery_repartition_rr);
+        let subquery_sort = sort_exec(sort_exprs, subquery_repartition_hash, true);
+        // The decorrelated subquery is joined with the outer query on the
+        // correlated column:
+        let join = hash_join_exec(outer_repartition_hash, subquery_sort);
+        let outer_sort_exprs = vec![PhysicalSortExpr {
+            expr: Arc::new(Column::new("a", 3)),
+            options: SortOptions {
+                descending: false,
+                nulls_first: false,
+            },
+        }];
+        let physical_plan = sort_preserving_merge_exec(outer_sort_exprs, join);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@3 ASC NULLS LAST]",
+            "  HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+            "    SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The repartition inside the subquery is converted independently of the
+        // outer query, and the join on the correlated column is kept as is:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(Arc::clone(&physical_plan), &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@3 ASC NULLS LAST]",
+            "  HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        let original_join = physical_plan.children()[0]
+            .as_any()
+            .downcast_ref::<HashJoinExec>()
+            .unwrap();
+        let optimized_join = optimized.children()[0]
+            .as_any()
+            .downcast_ref::<HashJoinExec>()
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", optimized_join.on()),
+            format!("{:?}", original_join.on())
+        );
+        assert_eq!(optimized_join.schema(), original_join.schema());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keep_sort_when_statistics_make_merging_expensive() -> Result<()> {
+        let schema = create_test_schema()?;
+        let columns = (0..4)
+            .map(|_| Arc::new(Int32Array::from_iter_values(0..10)) as _)
+            .collect();
+        let batch = RecordBatch::try_new(Arc::clone(&schema), columns)?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = Arc::new(
+            MemoryExec::try_new(&[vec![batch]], Arc::clone(&schema), None)?
+                .with_sort_information(vec![sort_exprs.clone()]),
+        );
+        let repartition = repartition_exec_hash(repartition_exec_round_robin(source));
+        let sort = sort_exec(sort_exprs.clone(), repartition, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@2], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        MemoryExec: partitions=1, partition_sizes=[1], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected);
+
+        // Preserving the ordering through the hash repartition would satisfy
+        // the sort, but merging 10 rows from 8 partitions is estimated to be
+        // more expensive than sorting about a row per partition:
+        let sort = physical_plan.children()[0]
+            .as_any()
+            .downcast_ref::<SortExec>()
+            .unwrap();
+        let candidate = Arc::new(
+            RepartitionExec::try_new(
+                Arc::clone(sort.input().children()[0]),
+                sort.input().output_partitioning().clone(),
+            )?
+            .with_preserve_order(),
+        ) as Arc<dyn ExecutionPlan>;
+        assert!(candidate
+            .equivalence_properties()
+            .ordering_satisfy(sort.expr()));
+        let (merge_cost, sort_cost) = order_preserving_cost(&candidate, sort).unwrap();
+        assert!(merge_cost > sort_cost);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        assert_eq!(get_plan_string(&optimized), expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_partial_sort_over_prefix_preserving_repartition() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![sort_expr("a", &schema)]);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort_exprs = vec![
+            sort_expr("a", &repartition_hash.schema()),
+            sort_expr("d", &repartition_hash.schema()),
+        ];
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST,d@2 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST,d@2 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // Preserving the ordering on `a` through the hash repartition leaves
+        // only the rows with equal `a` values to be sorted on `d`:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST,d@2 ASC NULLS LAST]",
+            "  PartialSortExec: expr=[a@0 ASC NULLS LAST,d@2 ASC NULLS LAST], common_prefix_length=[1]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fused_merge_retains_parent_fetch() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let sort = sort_exec(sort_exprs.clone(), coalesce_partitions, false);
+        let physical_plan: Arc<dyn ExecutionPlan> = Arc::new(
+            SortPreservingMergeExec::new(sort_exprs, sort)
+                .with_fetch(Some(7))
+                .with_prefetch(3),
+        );
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=7",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "    CoalescePartitionsExec",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The merge replacing the coalesce is fused with the parent merge, and
+        // the fused merge keeps the options of the parent:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=7",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        let merge = optimized
+            .as_any()
+            .downcast_ref::<SortPreservingMergeExec>()
+            .unwrap();
+        assert_eq!(merge.fetch(), Some(7));
+        assert_eq!(merge.prefetch(), 3);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_prefer_conversion_over_spilling_sort(
+        #[values(1 << 10, 1 << 30)] total_byte_size: usize,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let ordered_source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let mut statistics = Statistics::new_unknown(&ordered_source.schema());
+        statistics.num_rows = Precision::Inexact(total_byte_size / 64);
+        statistics.total_byte_size = Precision::Inexact(total_byte_size);
+        let source = PassThroughTestExec::new(ordered_source)
+            .with_statistics(statistics)
+            .into_arc();
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        PassThroughTestExec",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The query is bounded and `prefer_existing_sort` is not set, so the
+        // sort is only removed if its input is estimated to exceed the memory
+        // reserved for sorting:
+        let config = ConfigOptions::new();
+        let spills = total_byte_size > config.execution.sort_spill_reservation_bytes;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = if spills {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      PassThroughTestExec",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_single_target_hash_repartition(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs.clone())
+        } else {
+            csv_exec_sorted(&schema, sort_exprs.clone())
+        };
+        let hash_exprs = vec![col("c", &source.schema())?];
+        let repartition_hash = Arc::new(RepartitionExec::try_new(
+            source,
+            Partitioning::Hash(hash_exprs, 1),
+        )?);
+        let physical_plan = sort_exec(sort_exprs, repartition_hash, true);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "  RepartitionExec: partitioning=Hash([c@1], 1), input_partitions=1",
+            "    StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "  RepartitionExec: partitioning=Hash([c@1], 1), input_partitions=1",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "RepartitionExec: partitioning=Hash([c@1], 1), input_partitions=1",
+            "  StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = [
+            "RepartitionExec: partitioning=Hash([c@1], 1), input_partitions=1",
+            "  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized_bounded_sort_preserve = expected_optimized_bounded;
+
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_record_sort_removal_decisions() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        // The ordering of the first source is lost by repartitions, while the
+        // second source is not ordered at all and needs to be sorted before
+        // the same repartitions:
+        let source_1 = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr_1 = repartition_exec_round_robin(source_1);
+        let repartition_hash_1 = repartition_exec_hash(repartition_rr_1);
+        let sort_1 = sort_exec(sort_exprs.clone(), repartition_hash_1, true);
+        let source_2 = csv_exec_sorted(&schema, vec![]);
+        let sort_2 = sort_exec(sort_exprs.clone(), source_2, false);
+        let repartition_rr_2 = repartition_exec_round_robin(sort_2);
+        let repartition_hash_2 = repartition_exec_hash(repartition_rr_2);
+        let sort_3 = sort_exec(sort_exprs, repartition_hash_2, true);
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            Arc::new(UnionExec::new(vec![sort_1, sort_3]));
+
+        let rule = ReplaceWithOrderPreservingVariants::new(true, true);
+        let config = ConfigOptions::new();
+        let (optimized, decisions) =
+            rule.optimize_and_record_decisions(Arc::clone(&physical_plan), &config)?;
+
+        let expected_optimized = [
+            "UnionExec",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        // The decisions do not affect the optimized plan:
+        assert_eq!(
+            get_plan_string(&rule.optimize(physical_plan, &config)?),
+            expected_optimized
+        );
+
+        let removed = SortOutcome::Removed(SortRemovalReason::OrderPreservingVariants);
+        assert_eq!(
+            decisions,
+            [
+                SortRemovalDecision {
+                    path: vec![0],
+                    outcome: removed.clone(),
+                },
+                SortRemovalDecision {
+                    path: vec![1, 0, 0, 0],
+                    outcome: SortOutcome::Kept(SortKeptReason::NoOrderingConnection),
+                },
+                SortRemovalDecision {
+                    path: vec![1],
+                    outcome: removed,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_sort_merge_join_over_repartitioned_inputs(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let join_input = || {
+            let source = if source_unbounded {
+                stream_exec_ordered(&schema, sort_exprs.clone())
+            } else {
+                csv_exec_sorted(&schema, sort_exprs.clone())
+            };
+            let repartition_rr = repartition_exec_round_robin(source);
+            let repartition_hash = repartition_exec_hash(repartition_rr);
+            sort_exec(sort_exprs.clone(), repartition_hash, true)
+        };
+        let join_on = vec![(
+            Arc::new(Column::new("a", 0)) as _,
+            Arc::new(Column::new("a", 0)) as _,
+        )];
+        let physical_plan =
+            sort_merge_join_exec(join_input(), join_input(), &join_on, &JoinType::Inner);
+
+        // Expected inputs unbounded and bounded
+        let expected_input_unbounded = [
+            "SortMergeJoin: join_type=Inner, on=[(a@0, a@0)]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        let expected_input_bounded = [
+            "SortMergeJoin: join_type=Inner, on=[(a@0, a@0)]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        // Expected unbounded result (same for with and without flag)
+        let expected_optimized_unbounded = [
+            "SortMergeJoin: join_type=Inner, on=[(a@0, a@0)]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+
+        // Expected bounded results with and without flag
+        let expected_optimized_bounded = expected_input_bounded;
+        let expected_optimized_bounded_sort_preserve = [
+            "SortMergeJoin: join_type=Inner, on=[(a@0, a@0)]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+
+        assert_optimized_in_all_boundedness_situations!(
+            expected_input_unbounded,
+            expected_input_bounded,
+            expected_optimized_unbounded,
+            expected_optimized_bounded,
+            expected_optimized_bounded_sort_preserve,
+            physical_plan,
+            source_unbounded
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_clustered_sink() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, false);
+        let sort_order = PhysicalSortRequirement::from_sort_exprs(&sort_exprs);
+        let physical_plan = data_sink_exec(sort, Some(sort_order));
+
+        // The sort that orders the rows for a table clustered on `a`:
+        let expected_input = [
+            "DataSinkExec: sink=TestSink",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The sort also merges the partitions of its input, so it is replaced
+        // by a merge of the order-preserving repartition:
+        let optimized =
+            optimize_with_config(Arc::clone(&physical_plan), &ConfigOptions::new())?;
+        let expected_optimized = [
+            "DataSinkExec: sink=TestSink",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // The merge is configured like the other merges of the rule:
+        let mut config = ConfigOptions::new();
+        config.execution.sort_preserving_merge_batch_prefetch = 4;
+        config.optimizer.annotate_order_preserving_variants = true;
+        let optimized = optimize_with_config(Arc::clone(&physical_plan), &config)?;
+        let merge = optimized.children()[0]
+            .as_any()
+            .downcast_ref::<SortPreservingMergeExec>()
+            .unwrap();
+        assert_eq!(merge.prefetch(), 4);
+        assert_eq!(merge.annotation(), Some(ORDER_PRESERVING_ANNOTATION));
+
+        // A merge over the fan-in budget is declined:
+        let mut config = ConfigOptions::new();
+        config.optimizer.max_order_preserving_merge_fanin = Some(4);
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        assert_eq!(get_plan_string(&optimized), expected_input);
+
+        // Without an ordered source, the original sort is kept:
+        let source = csv_exec_sorted(&schema, vec![]);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, false);
+        let sort_order = PhysicalSortRequirement::from_sort_exprs(&sort_exprs);
+        let physical_plan = data_sink_exec(sort, Some(sort_order));
+        let expected_input = [
+            "DataSinkExec: sink=TestSink",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+        let optimized = optimize_with_config(physical_plan, &ConfigOptions::new())?;
+        assert_eq!(get_plan_string(&optimized), expected_input);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_limit_pushdown_after_conversion(
+        #[values(false, true)] prefer_existing_sort: bool,
+    ) -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("c", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..1000)),
+                Arc::new(Int32Array::from_iter_values((0..1000).map(|i| i % 3))),
+            ],
+        )?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = Arc::new(
+            MemoryExec::try_new(&[vec![batch]], Arc::clone(&schema), None)?
+                .with_sort_information(vec![sort_exprs.clone()]),
+        );
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let spm = sort_preserving_merge_exec(sort_exprs, sort);
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            Arc::new(GlobalLimitExec::new(spm, 0, Some(3)));
+
+        let expected_input = [
+            "GlobalLimitExec: skip=0, fetch=3",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          MemoryExec: partitions=1, partition_sizes=[1], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // Run `LimitPushdown` after the sub-rule, which pushes the limit into
+        // the merge, and into the sort of each partition if it is kept:
+        let config = SessionConfig::new().with_prefer_existing_sort(prefer_existing_sort);
+        let optimized = optimize_with_config(physical_plan, config.options())?;
+        let optimized = LimitPushdown::new().optimize(optimized, config.options())?;
+        let expected_optimized = if prefer_existing_sort {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=3",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      MemoryExec: partitions=1, partition_sizes=[1], output_ordering=a@0 ASC NULLS LAST",
+            ]
+        } else {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=3",
+                "  SortExec: TopK(fetch=3), expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        MemoryExec: partitions=1, partition_sizes=[1], output_ordering=a@0 ASC NULLS LAST",
+            ]
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        let task_ctx = SessionContext::new_with_config(config).task_ctx();
+        let batches = collect(optimized, task_ctx).await?;
+        let expected = [
+            "+---+---+",
+            "| a | c |",
+            "+---+---+",
+            "| 0 | 0 |",
+            "| 1 | 1 |",
+            "| 2 | 2 |",
+            "+---+---+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replace_multiple_input_repartition_under_memory_limit() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("c", DataType::Int32, false),
+        ]));
+        let batches = (0..10)
+            .map(|i| {
+                RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![
+                        Arc::new(Int32Array::from_iter_values(i * 1000..(i + 1) * 1000)),
+                        Arc::new(Int32Array::from_iter_values((0..1000).map(|j| j % 7))),
+                    ],
+                )
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = Arc::new(
+            MemoryExec::try_new(&[batches], Arc::clone(&schema), None)?
+                .with_sort_information(vec![sort_exprs.clone()]),
+        );
+        let repartition = repartition_exec_hash(repartition_exec_round_robin(source));
+        let sort = sort_exec(sort_exprs.clone(), repartition, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let config = SessionConfig::new().with_prefer_existing_sort(true);
+        let optimized = optimize_with_config(physical_plan, config.options())?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      MemoryExec: partitions=1, partition_sizes=[10], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // The order-preserving variants only buffer a batch or so per stream,
+        // so the plan must run to completion under a modest memory cap:
+        let pool = Arc::new(GreedyMemoryPool::new(1024 * 1024));
+        let runtime = RuntimeEnvBuilder::new()
+            .with_memory_pool(Arc::clone(&pool) as _)
+            .build_arc()?;
+        let task_ctx = SessionContext::new_with_config_rt(config, runtime).task_ctx();
+        let batches = collect(optimized, task_ctx).await?;
+
+        let values = batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column(0).as_any().downcast_ref::<Int32Array>();
+                column.unwrap().values().to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, (0..10_000).collect::<Vec<_>>());
+        assert_eq!(pool.reserved(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fuse_stacked_merges_of_alternate() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let spm = sort_preserving_merge_exec(sort_exprs.clone(), repartition_hash);
+        let coalesce_partitions = coalesce_partitions_exec(spm);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
 
-            // Get string representation of the plan
-            let actual = get_plan_string(&optimized_physical_plan);
-            assert_eq!(
-                expected_optimized_lines, actual,
-                "\n**Optimized Plan Mismatch\n\nexpected:\n\n{expected_optimized_lines:#?}\nactual:\n\n{actual:#?}\n\n"
-            );
-        };
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        // The merge replacing the coalesce would merge the single sorted stream
+        // of the existing merge again, so the two merges are fused:
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
     }
 
     #[rstest]
     #[tokio::test]
-    // Searches for a simple sort and a repartition just after it, the second repartition with 1 input partition should not be affected
-    async fn test_replace_multiple_input_repartition_1(
+    async fn test_max_order_preserving_merge_fanin(
         #[values(false, true)] source_unbounded: bool,
+        #[values(None, Some(8), Some(3))] max_fanin: Option<usize>,
     ) -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
         let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
+            stream_exec_ordered(&schema, sort_exprs.clone())
         } else {
-            csv_exec_sorted(&schema, sort_exprs)
+            csv_exec_sorted(&schema, sort_exprs.clone())
         };
-        let repartition = repartition_exec_hash(repartition_exec_round_robin(source));
-        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition, true);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
 
-        let physical_plan =
-            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+        let source_line = if source_unbounded {
+            "StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]"
+        } else {
+            "CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true"
+        };
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.max_order_preserving_merge_fanin = max_fanin;
+        let optimized = optimize_with_config(physical_plan, &config)?;
 
-        // Expected inputs unbounded and bounded
-        let expected_input_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        // The sort-preserving repartition would merge 8 streams, so a budget of
+        // 3 streams declines the conversion in the bounded case, and merges the
+        // streams in groups of 3 before the repartition in the unbounded case.
+        // The existing merge at the root is left as is:
+        let expected_optimized = match (max_fanin, source_unbounded) {
+            (Some(3), false) => vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]".to_string(),
+                "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]".to_string(),
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8".to_string(),
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1".to_string(),
+                format!("        {source_line}"),
+            ],
+            (Some(3), true) => vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]".to_string(),
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=3, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST".to_string(),
+                "    GroupedSortPreservingMergeExec: [a@0 ASC NULLS LAST], group_size=3".to_string(),
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1".to_string(),
+                format!("        {source_line}"),
+            ],
+            _ => vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]".to_string(),
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST".to_string(),
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1".to_string(),
+                format!("      {source_line}"),
+            ],
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_record_sort_blocked_by_fanin_cap() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        // The sort-preserving repartition would merge 8 streams, which is over
+        // the budget of 3 streams:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.max_order_preserving_merge_fanin = Some(3);
+        let (optimized, decisions) =
+            ReplaceWithOrderPreservingVariants::new(false, false)
+                .optimize_and_record_decisions(Arc::clone(&physical_plan), &config)?;
+        assert_eq!(get_plan_string(&optimized), get_plan_string(&physical_plan));
+        assert_eq!(
+            decisions,
+            [SortRemovalDecision {
+                path: vec![0],
+                outcome: SortOutcome::Kept(SortKeptReason::BlockedByFaninCap {
+                    partitions: 8,
+                    cap: 3,
+                }),
+            }]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_with_mixed_sort_directions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let batch = |a: Vec<Option<i32>>, b: Vec<Option<i32>>| -> Result<_> {
+            Ok(RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+            )?)
+        };
+        // Each partition is sorted on `[a ASC NULLS LAST, b DESC NULLS FIRST]`:
+        let partitions = vec![
+            vec![batch(
+                vec![Some(1), Some(1), Some(2), None],
+                vec![None, Some(3), Some(1), Some(5)],
+            )?],
+            vec![batch(
+                vec![Some(1), Some(2), Some(2), None],
+                vec![Some(4), None, Some(2), None],
+            )?],
         ];
-        let expected_input_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        let sort_exprs = vec![
+            sort_expr_options(
+                "a",
+                &schema,
+                SortOptions {
+                    descending: false,
+                    nulls_first: false,
+                },
+            ),
+            sort_expr_options(
+                "b",
+                &schema,
+                SortOptions {
+                    descending: true,
+                    nulls_first: true,
+                },
+            ),
         ];
+        let source = Arc::new(
+            MemoryExec::try_new(&partitions, Arc::clone(&schema), None)?
+                .with_sort_information(vec![sort_exprs.clone()]),
+        );
+        let coalesce_partitions = coalesce_partitions_exec(source);
+        let physical_plan = sort_exec(sort_exprs.clone(), coalesce_partitions, false);
 
-        // Expected unbounded result (same for with and without flag)
-        let expected_optimized_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        let config = SessionConfig::new().with_prefer_existing_sort(true);
+        let optimized = optimize_with_config(physical_plan, config.options())?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST,b@1 DESC]",
+            "  MemoryExec: partitions=2, partition_sizes=[1, 1], output_ordering=a@0 ASC NULLS LAST,b@1 DESC",
         ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        let merge = optimized
+            .as_any()
+            .downcast_ref::<SortPreservingMergeExec>()
+            .unwrap();
+        assert_eq!(merge.expr(), sort_exprs);
 
-        // Expected bounded results with and without flag
-        let expected_optimized_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
-        let expected_optimized_bounded_sort_preserve = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        let task_ctx = SessionContext::new_with_config(config).task_ctx();
+        let batches = collect(optimized, task_ctx).await?;
+        let expected = [
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 1 |   |",
+            "| 1 | 4 |",
+            "| 1 | 3 |",
+            "| 2 |   |",
+            "| 2 | 2 |",
+            "| 2 | 1 |",
+            "|   |   |",
+            "|   | 5 |",
+            "+---+---+",
         ];
-        assert_optimized_in_all_boundedness_situations!(
-            expected_input_unbounded,
-            expected_input_bounded,
-            expected_optimized_unbounded,
-            expected_optimized_bounded,
-            expected_optimized_bounded_sort_preserve,
-            physical_plan,
-            source_unbounded
-        );
+        assert_batches_eq!(expected, &batches);
         Ok(())
     }
 
     #[rstest]
     #[tokio::test]
-    async fn test_with_inter_children_change_only(
-        #[values(false, true)] source_unbounded: bool,
+    async fn test_coalesce_final_merge_output(
+        #[values(false, true)] coalesce_final_merge_output: bool,
     ) -> Result<()> {
         let schema = create_test_schema()?;
-        let sort_exprs = vec![sort_expr_default("a", &schema)];
-        let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
-        } else {
-            csv_exec_sorted(&schema, sort_exprs)
-        };
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
         let repartition_rr = repartition_exec_round_robin(source);
         let repartition_hash = repartition_exec_hash(repartition_rr);
         let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
-        let sort = sort_exec(
-            vec![sort_expr_default("a", &coalesce_partitions.schema())],
-            coalesce_partitions,
-            false,
-        );
-        let repartition_rr2 = repartition_exec_round_robin(sort);
-        let repartition_hash2 = repartition_exec_hash(repartition_rr2);
-        let filter = filter_exec(repartition_hash2);
-        let sort2 =
-            sort_exec(vec![sort_expr_default("a", &filter.schema())], filter, true);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
 
-        let physical_plan = sort_preserving_merge_exec(
-            vec![sort_expr_default("a", &sort2.schema())],
-            sort2,
-        );
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.coalesce_final_merge_output = coalesce_final_merge_output;
+        config.execution.batch_size = 1000;
+        let rule = ReplaceWithOrderPreservingVariants::new(false, true);
+        let optimized = rule.optimize(physical_plan, &config)?;
+        let expected_merge = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized = if coalesce_final_merge_output {
+            vec![
+                "CoalesceBatchesExec: target_batch_size=1000",
+                "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_merge.to_vec()
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
 
-        // Expected inputs unbounded and bounded
-        let expected_input_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          SortExec: expr=[a@0 ASC], preserve_partitioning=[false]",
-            "            CoalescePartitionsExec",
-            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "                  StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC]",
+        // A merge that is already at the root of the plan is not wrapped:
+        let merge = optimized
+            .as_any()
+            .downcast_ref::<CoalesceBatchesExec>()
+            .map_or(Arc::clone(&optimized), |coalesce| {
+                Arc::clone(coalesce.input())
+            });
+        let optimized = rule.optimize(merge, &config)?;
+        assert_eq!(get_plan_string(&optimized), expected_merge);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_reordering_projection() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![sort_expr("a", &schema)]);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        // Reorder the columns `[a, c, d]` to `[c, a, d]`:
+        let projection_exprs = vec![
+            (Arc::new(Column::new("c", 1)) as _, "c".to_string()),
+            (Arc::new(Column::new("a", 0)) as _, "a".to_string()),
+            (Arc::new(Column::new("d", 2)) as _, "d".to_string()),
         ];
-        let expected_input_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
-            "    FilterExec: c@1 > 3",
+        let projection: Arc<dyn ExecutionPlan> =
+            Arc::new(ProjectionExec::try_new(projection_exprs, repartition_hash)?);
+        let sort_exprs = vec![sort_expr("a", &projection.schema())];
+        let sort = sort_exec(sort_exprs.clone(), projection, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@1 ASC NULLS LAST]",
+            "  SortExec: expr=[a@1 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    ProjectionExec: expr=[c@1 as c, a@0 as a, d@2 as d]",
             "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
             "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          SortExec: expr=[a@0 ASC], preserve_partitioning=[false]",
-            "            CoalescePartitionsExec",
-            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "                  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC], has_header=true",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
 
-        // Expected unbounded result (same for with and without flag)
-        let expected_optimized_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  FilterExec: c@1 > 3",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC",
+        // The ordering of the converted repartition on `a@0` is mapped to
+        // `a@1` by the projection, which satisfies the sort:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@1 ASC NULLS LAST]",
+            "  ProjectionExec: expr=[c@1 as c, a@0 as a, d@2 as d]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
             "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        SortPreservingMergeExec: [a@0 ASC]",
-            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC",
-            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "              StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC]",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
 
-        // Expected bounded results with and without flag
-        let expected_optimized_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
-            "    FilterExec: c@1 > 3",
+    #[rstest]
+    #[tokio::test]
+    async fn test_prefer_existing_sort_when_free(
+        #[values(false, true)] prefer_existing_sort_when_free: bool,
+        #[values(false, true)] multi_partition_input: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let coalesce_input = if multi_partition_input {
+            repartition_exec_hash(repartition_exec_round_robin(source))
+        } else {
+            source
+        };
+        let coalesce_partitions = coalesce_partitions_exec(coalesce_input);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+
+        let expected_input = if multi_partition_input {
+            vec![
+                "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+                "  CoalescePartitionsExec",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            vec![
+                "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+                "  CoalescePartitionsExec",
+                "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        };
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The coalesce is only replaced if its input has a single partition,
+        // as merging a single partition is free:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort_when_free = prefer_existing_sort_when_free;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = if prefer_existing_sort_when_free
+            && !multi_partition_input
+        {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_non_deterministic_partitioning(
+        #[values(false, true)] deterministic: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_batches = coalesce_batches_exec(repartition_hash);
+        let sort = sort_exec(sort_exprs.clone(), coalesce_batches, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    CoalesceBatchesExec: target_batch_size=8192",
             "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
             "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          SortExec: expr=[a@0 ASC], preserve_partitioning=[false]",
-            "            CoalescePartitionsExec",
-            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "                  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC], has_header=true",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-        let expected_optimized_bounded_sort_preserve = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  FilterExec: c@1 > 3",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC",
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The repartition below a node with non-deterministic partitioning is
+        // not converted, even if `prefer_existing_sort` is set:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let mut rule = ReplaceWithOrderPreservingVariants::new(false, false);
+        if !deterministic {
+            rule = rule.with_hints(Arc::new(NonDeterministicCoalesceBatchesHints));
+        }
+        let optimized = rule.optimize(physical_plan, &config)?;
+        let expected_optimized = if deterministic {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  CoalesceBatchesExec: target_batch_size=8192",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_streaming_aggregate_on_event_time() -> Result<()> {
+        // The source is ordered on the event time `a`:
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = stream_exec_ordered(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        // The sort restores the event-time ordering, which lets the aggregate
+        // emit the groups of the past event times as the input advances:
+        let sort = sort_exec(sort_exprs, repartition_hash, true);
+        let group_by = PhysicalGroupBy::new_single(vec![(
+            col("a", &sort.schema())?,
+            "a".to_string(),
+        )]);
+        let physical_plan: Arc<dyn ExecutionPlan> = Arc::new(AggregateExec::try_new(
+            AggregateMode::SinglePartitioned,
+            group_by,
+            vec![],
+            vec![],
+            Arc::clone(&sort),
+            sort.schema(),
+        )?);
+
+        let expected_input = [
+            "AggregateExec: mode=SinglePartitioned, gby=[a@0 as a], aggr=[], ordering_mode=Sorted",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
             "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        SortPreservingMergeExec: [a@0 ASC]",
-            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC",
-            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC], has_header=true",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
-        assert_optimized_in_all_boundedness_situations!(
-            expected_input_unbounded,
-            expected_input_bounded,
-            expected_optimized_unbounded,
-            expected_optimized_bounded,
-            expected_optimized_bounded_sort_preserve,
-            physical_plan,
-            source_unbounded
-        );
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The sort breaks the pipeline of the unbounded query, so it is removed
+        // by preserving the event-time ordering through the repartition, and
+        // the aggregate keeps streaming its results:
+        let optimized = optimize_with_config(physical_plan, &ConfigOptions::new())?;
+        let expected_optimized = [
+            "AggregateExec: mode=SinglePartitioned, gby=[a@0 as a], aggr=[], ordering_mode=Sorted",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert!(optimized.execution_mode().is_unbounded());
+        assert!(optimized.execution_mode().pipeline_friendly());
         Ok(())
     }
 
     #[rstest]
     #[tokio::test]
-    async fn test_replace_multiple_input_repartition_2(
+    async fn test_replace_top_k_over_coalesce_partitions(
         #[values(false, true)] source_unbounded: bool,
     ) -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
         let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
+            stream_exec_ordered(&schema, sort_exprs.clone())
         } else {
-            csv_exec_sorted(&schema, sort_exprs)
+            csv_exec_sorted(&schema, sort_exprs.clone())
         };
         let repartition_rr = repartition_exec_round_robin(source);
-        let filter = filter_exec(repartition_rr);
-        let repartition_hash = repartition_exec_hash(filter);
-        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition_hash, true);
-
-        let physical_plan =
-            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            Arc::new(SortExec::new(sort_exprs, coalesce_partitions).with_fetch(Some(10)));
 
         // Expected inputs unbounded and bounded
         let expected_input_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "SortExec: TopK(fetch=10), expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
             "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
-        let expected_input_bounded =  [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+        let expected_input_bounded = [
+            "SortExec: TopK(fetch=10), expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
             "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
 
-        // Expected unbounded result (same for with and without flag)
-        let expected_optimized_unbounded =  [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+        // The merge that replaces the TopK keeps its fetch, so that it stops
+        // after emitting the first 10 rows:
+        let expected_optimized_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=10",
             "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
 
         // Expected bounded results with and without flag
-        let expected_optimized_bounded =  [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+        let expected_optimized_bounded = [
+            "SortExec: TopK(fetch=10), expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
             "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
         let expected_optimized_bounded_sort_preserve = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=10",
             "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
         assert_optimized_in_all_boundedness_situations!(
             expected_input_unbounded,
@@ -652,566 +7656,1212 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_replace_sort_with_reversed_scan() -> Result<()> {
+        let schema = create_test_schema()?;
+        let asc_nulls_last = SortOptions {
+            descending: false,
+            nulls_first: false,
+        };
+        let desc_nulls_first = SortOptions {
+            descending: true,
+            nulls_first: true,
+        };
+        let desc_nulls_last = SortOptions {
+            descending: true,
+            nulls_first: false,
+        };
+        let source_ordering = vec![sort_expr_options("a", &schema, asc_nulls_last)];
+
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false)
+            .with_hints(Arc::new(ReversibleMemoryHints));
+
+        // The sort requires the exact reverse of the ordering of the source,
+        // which can scan its partition backwards:
+        let source = memory_exec_sorted(&schema, source_ordering.clone(), 1)?;
+        let filter = filter_exec(source);
+        let physical_plan = sort_exec(
+            vec![sort_expr_options("a", &schema, desc_nulls_first)],
+            filter,
+            false,
+        );
+        let expected_input = [
+            "SortExec: expr=[a@0 DESC], preserve_partitioning=[false]",
+            "  FilterExec: c@2 > 3",
+            "    MemoryExec: partitions=1, partition_sizes=[0], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+        let optimized = rule.optimize(physical_plan, &ConfigOptions::new())?;
+        let expected_optimized = [
+            "FilterExec: c@2 > 3",
+            "  MemoryExec: partitions=1, partition_sizes=[0], output_ordering=a@0 DESC",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // The reversed partitions are merged if the sort merges them:
+        let source = memory_exec_sorted(&schema, source_ordering.clone(), 4)?;
+        let physical_plan = sort_exec(
+            vec![sort_expr_options("a", &schema, desc_nulls_first)],
+            source,
+            false,
+        );
+        let optimized =
+            rule.optimize(Arc::clone(&physical_plan), &ConfigOptions::new())?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 DESC]",
+            "  MemoryExec: partitions=4, partition_sizes=[0, 0, 0, 0], output_ordering=a@0 DESC",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // The merge is split into a cascade within the fan-in budget:
+        let mut config = ConfigOptions::new();
+        config.optimizer.max_order_preserving_merge_fanin = Some(2);
+        let optimized = rule.optimize(Arc::clone(&physical_plan), &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 DESC]",
+            "  GroupedSortPreservingMergeExec: [a@0 DESC], group_size=2",
+            "    MemoryExec: partitions=4, partition_sizes=[0, 0, 0, 0], output_ordering=a@0 DESC",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // The merge is vetoed by a replacement filter that rejects the sort:
+        let (optimized, decisions) = rule
+            .clone()
+            .with_replacement_filter(|plan| !plan.as_any().is::<SortExec>())
+            .optimize_and_record_decisions(
+                Arc::clone(&physical_plan),
+                &ConfigOptions::new(),
+            )?;
+        assert!(Arc::ptr_eq(&optimized, &physical_plan));
+        assert_eq!(
+            decisions[0].outcome,
+            SortOutcome::Kept(SortKeptReason::ReplacementRejected)
+        );
+
+        // Flipping only the direction does not reverse the ordering, as the
+        // nulls would still come last; so the sort is kept:
+        let source = memory_exec_sorted(&schema, source_ordering, 1)?;
+        let physical_plan = sort_exec(
+            vec![sort_expr_options("a", &schema, desc_nulls_last)],
+            source,
+            false,
+        );
+        let expected = [
+            "SortExec: expr=[a@0 DESC NULLS LAST], preserve_partitioning=[false]",
+            "  MemoryExec: partitions=1, partition_sizes=[0], output_ordering=a@0 ASC NULLS LAST",
+        ];
+        let optimized = rule.optimize(physical_plan, &ConfigOptions::new())?;
+        assert_eq!(get_plan_string(&optimized), expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_sort_established_by_window() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![sort_expr("a", &schema)]);
+        let source_schema = source.schema();
+        // `row_number() OVER (ORDER BY a)`:
+        let row_number = create_window_expr(
+            &WindowFunctionDefinition::WindowUDF(row_number_udwf()),
+            "row_number".to_string(),
+            &[],
+            &[],
+            &[sort_expr("a", &source_schema)],
+            Arc::new(WindowFrame::new(Some(false))),
+            source_schema.as_ref(),
+            false,
+        )?;
+        let window: Arc<dyn ExecutionPlan> = Arc::new(BoundedWindowAggExec::try_new(
+            vec![row_number],
+            source,
+            vec![],
+            InputOrderMode::Sorted,
+        )?);
+        let filter = filter_exec(window);
+        let sort_exprs = vec![sort_expr("row_number", &filter.schema())];
+        let physical_plan = sort_exec(sort_exprs, filter, false);
+
+        let expected_input = [
+            "SortExec: expr=[row_number@3 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  FilterExec: c@1 > 3",
+            "    BoundedWindowAggExec: wdw=[row_number: Ok(Field { name: \"row_number\", data_type: UInt64, nullable: false, dict_id: 0, dict_is_ordered: false, metadata: {} }), frame: WindowFrame { units: Range, start_bound: Preceding(NULL), end_bound: CurrentRow, is_causal: false }], mode=[Sorted]",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The window emits its rows ordered by `row_number`, so the sort is
+        // removed even though there is no repartition to convert below it:
+        let optimized = optimize_with_config(physical_plan, &ConfigOptions::new())?;
+        let expected_optimized = [
+            "FilterExec: c@1 > 3",
+            "  BoundedWindowAggExec: wdw=[row_number: Ok(Field { name: \"row_number\", data_type: UInt64, nullable: false, dict_id: 0, dict_is_ordered: false, metadata: {} }), frame: WindowFrame { units: Range, start_bound: Preceding(NULL), end_bound: CurrentRow, is_causal: false }], mode=[Sorted]",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_repartition_shared_by_ordered_and_unordered_consumers(
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        // The same repartition feeds both a sorted and an unsorted branch, so
+        // the plan is a DAG rather than a tree:
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), Arc::clone(&repartition_hash), true);
+        let ordered = sort_preserving_merge_exec(sort_exprs, sort);
+        let unordered = coalesce_partitions_exec(Arc::clone(&repartition_hash));
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            Arc::new(UnionExec::new(vec![ordered, unordered]));
+
+        let expected_input = [
+            "UnionExec",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // The shared repartition is split: the sorted branch gets its own
+        // order-preserving variant, while the unsorted branch keeps consuming
+        // the original repartition and does not pay for merging:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "UnionExec",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        let unordered_input = &optimized.children()[1].children()[0];
+        assert!(Arc::ptr_eq(unordered_input, &repartition_hash));
+        Ok(())
+    }
+
     #[rstest]
     #[tokio::test]
-    async fn test_replace_multiple_input_repartition_with_extra_steps(
+    async fn test_preserve_order_repartition_batch_size(
+        #[values(None, Some(64))] batch_size: Option<usize>,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.preserve_order_repartition_batch_size = batch_size;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+
+        // The batch size of the merge is only displayed when it is configured:
+        let repartition_line = match batch_size {
+            None => "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            Some(_) => "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST, preserve_order_batch_size=64",
+        };
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            repartition_line,
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        let repartition = optimized.children()[0]
+            .as_any()
+            .downcast_ref::<RepartitionExec>()
+            .unwrap();
+        assert_eq!(repartition.preserve_order_batch_size(), batch_size);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_remove_sort_over_ordered_input(
         #[values(false, true)] source_unbounded: bool,
     ) -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
         let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
+            stream_exec_ordered(&schema, sort_exprs.clone())
         } else {
-            csv_exec_sorted(&schema, sort_exprs)
+            csv_exec_sorted(&schema, sort_exprs.clone())
         };
+        // The round-robin repartition of a single partition keeps its ordering
+        // in each output partition:
         let repartition_rr = repartition_exec_round_robin(source);
-        let repartition_hash = repartition_exec_hash(repartition_rr);
-        let filter = filter_exec(repartition_hash);
-        let coalesce_batches_exec: Arc<dyn ExecutionPlan> = coalesce_batches_exec(filter);
-        let sort = sort_exec(vec![sort_expr("a", &schema)], coalesce_batches_exec, true);
-
-        let physical_plan =
-            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+        let coalesce_batches = coalesce_batches_exec(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), coalesce_batches, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
 
-        // Expected inputs unbounded and bounded
         let expected_input_unbounded = [
             "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
             "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
             "    CoalesceBatchesExec: target_batch_size=8192",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
         let expected_input_bounded = [
             "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
             "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
             "    CoalesceBatchesExec: target_batch_size=8192",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
 
-        // Expected unbounded result (same for with and without flag)
+        // The sort is a no-op, so it is removed regardless of the flag and the
+        // repartition below it is left as is:
         let expected_optimized_unbounded = [
             "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
             "  CoalesceBatchesExec: target_batch_size=8192",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
-
-        // Expected bounded results with and without flag
         let expected_optimized_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    CoalesceBatchesExec: target_batch_size=8192",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
-        let expected_optimized_bounded_sort_preserve = [
             "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
             "  CoalesceBatchesExec: target_batch_size=8192",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
         assert_optimized_in_all_boundedness_situations!(
             expected_input_unbounded,
             expected_input_bounded,
             expected_optimized_unbounded,
             expected_optimized_bounded,
-            expected_optimized_bounded_sort_preserve,
+            expected_optimized_bounded,
             physical_plan,
             source_unbounded
         );
         Ok(())
     }
 
-    #[rstest]
     #[tokio::test]
-    async fn test_replace_multiple_input_repartition_with_extra_steps_2(
-        #[values(false, true)] source_unbounded: bool,
-    ) -> Result<()> {
+    async fn test_with_union_of_identically_sorted_inputs() -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
-        let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
-        } else {
-            csv_exec_sorted(&schema, sort_exprs)
+        let inputs = (0..3)
+            .map(|_| csv_exec_sorted(&schema, sort_exprs.clone()))
+            .collect();
+        let union = Arc::new(UnionExec::new(inputs));
+        let physical_plan = sort_exec(sort_exprs, union, false);
+
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  UnionExec",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        // The inputs of the union share their ordering, so the sort that merges
+        // the partitions of the union can be replaced by a merge:
+        let expected_optimized_sort_preserve = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  UnionExec",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_prefer_sort_on_off!(
+            expected_input,
+            expected_input,
+            expected_optimized_sort_preserve,
+            physical_plan
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_of_union_is_gated() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let inputs = (0..3)
+            .map(|_| csv_exec_sorted(&schema, sort_exprs.clone()))
+            .collect();
+        let union = Arc::new(UnionExec::new(inputs));
+        let physical_plan = sort_exec(sort_exprs, union, false);
+        let rule = ReplaceWithOrderPreservingVariants::new(false, true);
+        let decide = |rule: &ReplaceWithOrderPreservingVariants,
+                      config: &ConfigOptions|
+         -> Result<SortOutcome> {
+            let (optimized, decisions) =
+                rule.optimize_and_record_decisions(Arc::clone(&physical_plan), config)?;
+            assert_eq!(decisions.len(), 1);
+            let outcome = decisions[0].outcome.clone();
+            if matches!(outcome, SortOutcome::Kept(_)) {
+                assert!(Arc::ptr_eq(&optimized, &physical_plan));
+            }
+            Ok(outcome)
+        };
+
+        // By default, the partitions of the union are merged:
+        assert_eq!(
+            decide(&rule, &ConfigOptions::new())?,
+            SortOutcome::Removed(SortRemovalReason::OrderPreservingVariants)
+        );
+
+        // The merge is declined like the order-preserving variant of a
+        // `CoalescePartitionsExec`:
+        let mut config = ConfigOptions::new();
+        config.optimizer.enable_order_preserving_coalesce = false;
+        assert_eq!(
+            decide(&rule, &config)?,
+            SortOutcome::Kept(SortKeptReason::MergeDisabled)
+        );
+        let vetoing_rule = rule
+            .clone()
+            .with_replacement_filter(|plan| !plan.as_any().is::<SortExec>());
+        assert_eq!(
+            decide(&vetoing_rule, &ConfigOptions::new())?,
+            SortOutcome::Kept(SortKeptReason::ReplacementRejected)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_count_removed_and_converted_operators() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash_1 = repartition_exec_hash(repartition_rr);
+        let sort_1 = sort_exec(sort_exprs.clone(), repartition_hash_1, true);
+        let repartition_hash_2 = repartition_exec_hash(sort_1);
+        let repartition_hash_3 = repartition_exec_hash(repartition_hash_2);
+        let sort_2 = sort_exec(sort_exprs.clone(), repartition_hash_3, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort_2);
+
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false);
+        let (optimized, counts) = rule.optimize_and_count(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert_eq!(
+            counts,
+            OrderPreservingCounts {
+                sorts_removed: 2,
+                repartitions_converted: 3,
+                coalesces_converted: 0,
+                pipeline_friendly: true,
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_symmetric_hash_join_of_unbounded_streams() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        // Each side of the join is an ordered stream, which is repartitioned
+        // and then sorted again to satisfy the ordering required by the join:
+        let side = || {
+            let source = stream_exec_ordered(&schema, sort_exprs.clone());
+            let repartition_rr = repartition_exec_round_robin(source);
+            let repartition_hash = repartition_exec_hash(repartition_rr);
+            sort_exec(sort_exprs.clone(), repartition_hash, true)
         };
+        let (left, right) = (side(), side());
+        let on = vec![(col("c", &left.schema())?, col("c", &right.schema())?)];
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            Arc::new(SymmetricHashJoinExec::try_new(
+                left,
+                right,
+                on,
+                None,
+                &JoinType::Inner,
+                false,
+                Some(sort_exprs.clone()),
+                Some(sort_exprs.clone()),
+                StreamJoinPartitionMode::Partitioned,
+            )?);
+
+        let expected_input = [
+            "SymmetricHashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+        assert!(!physical_plan.execution_mode().pipeline_friendly());
+
+        // The sorts break the pipeline, so they are removed by preserving the
+        // orderings of the streams through the repartitions of both sides:
+        let optimized = optimize_with_config(physical_plan, &ConfigOptions::new())?;
+        let expected_optimized = [
+            "SymmetricHashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert!(optimized.execution_mode().pipeline_friendly());
+
+        // The join emits matches from both sides as they arrive, so it does not
+        // maintain the ordering of either side:
+        assert_eq!(optimized.maintains_input_order(), vec![false, false]);
+        assert!(optimized.output_ordering().is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_parent_requiring_hash_partitioning() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
         let repartition_rr = repartition_exec_round_robin(source);
-        let coalesce_batches_exec_1 = coalesce_batches_exec(repartition_rr);
-        let repartition_hash = repartition_exec_hash(coalesce_batches_exec_1);
-        let filter = filter_exec(repartition_hash);
-        let coalesce_batches_exec_2 = coalesce_batches_exec(filter);
-        let sort =
-            sort_exec(vec![sort_expr("a", &schema)], coalesce_batches_exec_2, true);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs, repartition_hash, true);
+        // The aggregate requires its input to be hash partitioned on `c`:
+        let group_by = PhysicalGroupBy::new_single(vec![(
+            col("c", &sort.schema())?,
+            "c".to_string(),
+        )]);
+        let physical_plan: Arc<dyn ExecutionPlan> = Arc::new(AggregateExec::try_new(
+            AggregateMode::FinalPartitioned,
+            group_by,
+            vec![],
+            vec![],
+            Arc::clone(&sort),
+            sort.schema(),
+        )?);
+
+        // The order-preserving repartition keeps the hash partitioning, so the
+        // requirement of the aggregate remains satisfied:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "AggregateExec: mode=FinalPartitioned, gby=[c@1 as c], aggr=[]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        let input = &optimized.children()[0];
+        let required = &optimized.required_input_distribution()[0];
+        assert!(input
+            .output_partitioning()
+            .satisfy(required, input.equivalence_properties()));
 
-        let physical_plan =
-            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+        // An alternate with the same number of partitions that is not hash
+        // partitioned on `c` would break the requirement, so it is declined:
+        let alternate = repartition_exec_round_robin(csv_exec_sorted(
+            &schema,
+            vec![sort_expr("a", &schema)],
+        ));
+        assert!(keeps_output_partitioning(&sort, &sort));
+        assert!(!keeps_output_partitioning(&sort, &alternate));
+        Ok(())
+    }
 
-        // Expected inputs unbounded and bounded
-        let expected_input_unbounded = [
+    #[tokio::test]
+    async fn test_with_nested_plans() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let subquery = sort_preserving_merge_exec(sort_exprs.clone(), sort);
+        // Both nodes evaluate the same sorted subquery:
+        let inner = PassThroughTestExec::new(csv_exec_sorted(&schema, sort_exprs))
+            .with_nested_plan(Arc::clone(&subquery))
+            .into_arc();
+        let physical_plan = PassThroughTestExec::new(inner)
+            .with_nested_plan(Arc::clone(&subquery))
+            .into_arc();
+
+        let expected_subquery = [
             "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
             "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    CoalesceBatchesExec: target_batch_size=8192",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          CoalesceBatchesExec: target_batch_size=8192",
-            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "              StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-        let expected_input_bounded = [
+        assert_eq!(get_plan_string(&subquery), expected_subquery);
+
+        // The nested plan is optimized like the plan itself:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false)
+            .with_hints(Arc::new(NestedPlanHints));
+        let optimized = rule.optimize(physical_plan, &config)?;
+        let expected_plan = [
+            "PassThroughTestExec",
+            "  PassThroughTestExec",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_plan);
+        let expected_optimized_subquery = [
             "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    CoalesceBatchesExec: target_batch_size=8192",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          CoalesceBatchesExec: target_batch_size=8192",
-            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
+        let outer_subquery = NestedPlanHints.nested_plans(optimized.as_ref())[0];
+        let inner_subquery =
+            NestedPlanHints.nested_plans(optimized.children()[0].as_ref())[0];
+        assert_eq!(get_plan_string(outer_subquery), expected_optimized_subquery);
+        // The shared nested plan is optimized only once:
+        assert!(Arc::ptr_eq(outer_subquery, inner_subquery));
+        Ok(())
+    }
 
-        // Expected unbounded result (same for with and without flag)
-        let expected_optimized_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  CoalesceBatchesExec: target_batch_size=8192",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "        CoalesceBatchesExec: target_batch_size=8192",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+    #[tokio::test]
+    async fn test_pipeline_friendly_with_unavoidable_sort() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = stream_exec_ordered(&schema, vec![sort_expr("a", &schema)]);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan = sort_exec(
+            vec![sort_expr("c", &coalesce_partitions.schema())],
+            coalesce_partitions,
+            false,
+        );
+
+        // No ordering in the plan satisfies the sort, so it stays:
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false);
+        let (optimized, counts) =
+            rule.optimize_and_count(Arc::clone(&physical_plan), &ConfigOptions::new())?;
+        let expected_optimized = [
+            "SortExec: expr=[c@1 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        assert!(!is_pipeline_friendly(&optimized));
+        assert!(!counts.pipeline_friendly);
+        Ok(())
+    }
 
-        // Expected bounded results with and without flag
-        let expected_optimized_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    CoalesceBatchesExec: target_batch_size=8192",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          CoalesceBatchesExec: target_batch_size=8192",
-            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+    #[tokio::test]
+    async fn test_prefer_existing_sort_override() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-        let expected_optimized_bounded_sort_preserve = [
+        let expected_optimized = [
             "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  CoalesceBatchesExec: target_batch_size=8192",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "        CoalesceBatchesExec: target_batch_size=8192",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-        assert_optimized_in_all_boundedness_situations!(
-            expected_input_unbounded,
-            expected_input_bounded,
-            expected_optimized_unbounded,
-            expected_optimized_bounded,
-            expected_optimized_bounded_sort_preserve,
-            physical_plan,
-            source_unbounded
-        );
+
+        // The override takes precedence over the config option either way:
+        let mut config = ConfigOptions::new();
+        for prefer_existing_sort in [false, true] {
+            config.optimizer.prefer_existing_sort = prefer_existing_sort;
+            let rule = ReplaceWithOrderPreservingVariants::new(false, false)
+                .with_prefer_existing_sort(Some(true));
+            let optimized = rule.optimize(Arc::clone(&physical_plan), &config)?;
+            assert_eq!(get_plan_string(&optimized), expected_optimized);
+            let rule = ReplaceWithOrderPreservingVariants::new(false, false)
+                .with_prefer_existing_sort(Some(false));
+            let optimized = rule.optimize(Arc::clone(&physical_plan), &config)?;
+            assert_eq!(get_plan_string(&optimized), expected_input);
+        }
         Ok(())
     }
 
     #[rstest]
     #[tokio::test]
-    async fn test_not_replacing_when_no_need_to_preserve_sorting(
-        #[values(false, true)] source_unbounded: bool,
+    async fn test_remove_single_input_round_robin(
+        #[values(1, 2)] source_partitions: usize,
     ) -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
-        let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let source = if source_partitions > 1 {
+            Arc::new(UnionExec::new(vec![source.clone(), source]))
         } else {
-            csv_exec_sorted(&schema, sort_exprs)
+            source
         };
         let repartition_rr = repartition_exec_round_robin(source);
         let repartition_hash = repartition_exec_hash(repartition_rr);
-        let filter = filter_exec(repartition_hash);
-        let coalesce_batches_exec: Arc<dyn ExecutionPlan> = coalesce_batches_exec(filter);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
 
-        let physical_plan: Arc<dyn ExecutionPlan> =
-            coalesce_partitions_exec(coalesce_batches_exec);
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.allow_order_preserving_round_robin = true;
+        config
+            .optimizer
+            .remove_round_robin_below_ordered_repartition = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        // The round-robin is only removed if it has a single input partition:
+        let expected_optimized = if source_partitions == 1 {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=1",
+                "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=2, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      UnionExec",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        };
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
 
-        // Expected inputs unbounded and bounded
-        let expected_input_unbounded = [
-            "CoalescePartitionsExec",
-            "  CoalesceBatchesExec: target_batch_size=8192",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
-        ];
-        let expected_input_bounded = [
-            "CoalescePartitionsExec",
-            "  CoalesceBatchesExec: target_batch_size=8192",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
+    #[tokio::test]
+    async fn test_log_traversal_stats() -> Result<()> {
+        use std::sync::Mutex;
 
-        // Expected unbounded result (same for with and without flag)
-        let expected_optimized_unbounded = [
-            "CoalescePartitionsExec",
-            "  CoalesceBatchesExec: target_batch_size=8192",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        /// Captures the log records of this module.
+        struct CapturingLogger {
+            messages: Mutex<Vec<String>>,
+        }
+
+        impl log::Log for CapturingLogger {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.target() == module_path!().trim_end_matches("::tests")
+            }
+
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    self.messages
+                        .lock()
+                        .unwrap()
+                        .push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: CapturingLogger = CapturingLogger {
+            messages: Mutex::new(vec![]),
+        };
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        ReplaceWithOrderPreservingVariants::new(false, false)
+            .optimize(physical_plan, &config)?;
+
+        // Other tests may log concurrently:
+        let messages = LOGGER.messages.lock().unwrap();
+        assert!(messages.iter().any(|message| message.starts_with(
+            "ReplaceWithOrderPreservingVariants visited 5 nodes and transformed 1 of them in "
+        )));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_filter_fixing_sort_column() -> Result<()> {
+        let schema = create_test_schema()?;
+        let source = csv_exec_sorted(&schema, vec![sort_expr("a", &schema)]);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let filter_schema = repartition_hash.schema();
+        let predicate = expressions::binary(
+            col("c", &filter_schema)?,
+            Operator::Eq,
+            expressions::lit(5i32),
+            &filter_schema,
+        )?;
+        let filter = Arc::new(FilterExec::try_new(predicate, repartition_hash)?);
+        let coalesce_partitions = coalesce_partitions_exec(filter);
+        let sort_exprs = vec![
+            sort_expr("c", &filter_schema),
+            sort_expr("a", &filter_schema),
         ];
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
 
-        // Expected bounded results same with and without flag, because there is no executor  with ordering requirement
-        let expected_optimized_bounded = [
-            "CoalescePartitionsExec",
-            "  CoalesceBatchesExec: target_batch_size=8192",
-            "    FilterExec: c@1 > 3",
+        // As `c` is constant after the filter, the ordering on `a` suffices:
+        let expected_input = [
+            "SortExec: expr=[c@1 ASC NULLS LAST,a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    FilterExec: c@1 = 5",
             "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
             "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
             "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-        let expected_optimized_bounded_sort_preserve = expected_optimized_bounded;
-
-        assert_optimized_in_all_boundedness_situations!(
-            expected_input_unbounded,
-            expected_input_bounded,
-            expected_optimized_unbounded,
-            expected_optimized_bounded,
-            expected_optimized_bounded_sort_preserve,
-            physical_plan,
-            source_unbounded
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  FilterExec: c@1 = 5",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_optimized_prefer_sort_on_off!(
+            expected_input,
+            expected_input,
+            expected_optimized,
+            physical_plan
         );
         Ok(())
     }
 
-    #[rstest]
     #[tokio::test]
-    async fn test_with_multiple_replacable_repartitions(
-        #[values(false, true)] source_unbounded: bool,
-    ) -> Result<()> {
+    async fn test_with_multiple_sorted_file_groups() -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
-        let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
-        } else {
-            csv_exec_sorted(&schema, sort_exprs)
-        };
-        let repartition_rr = repartition_exec_round_robin(source);
-        let repartition_hash = repartition_exec_hash(repartition_rr);
-        let filter = filter_exec(repartition_hash);
-        let coalesce_batches = coalesce_batches_exec(filter);
-        let repartition_hash_2 = repartition_exec_hash(coalesce_batches);
-        let sort = sort_exec(vec![sort_expr("a", &schema)], repartition_hash_2, true);
-
-        let physical_plan =
-            sort_preserving_merge_exec(vec![sort_expr("a", &schema)], sort);
+        let source = csv_exec_sorted_with_file_groups(&schema, sort_exprs.clone(), 2);
+        let physical_plan = sort_exec(sort_exprs, source, false);
 
-        // Expected inputs unbounded and bounded
-        let expected_input_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      CoalesceBatchesExec: target_batch_size=8192",
-            "        FilterExec: c@1 > 3",
-            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "              StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CsvExec: file_groups={2 groups: [[file_path_0], [file_path_1]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-        let expected_input_bounded = [
+        let expected_optimized = [
             "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      CoalesceBatchesExec: target_batch_size=8192",
-            "        FilterExec: c@1 > 3",
-            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  CsvExec: file_groups={2 groups: [[file_path_0], [file_path_1]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
+        assert_optimized_prefer_sort_on_off!(
+            expected_input,
+            expected_input,
+            expected_optimized,
+            physical_plan
+        );
+        Ok(())
+    }
 
-        // Expected unbounded result (same for with and without flag)
-        let expected_optimized_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "    CoalesceBatchesExec: target_batch_size=8192",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
-        ];
+    #[tokio::test]
+    async fn test_merge_of_sorted_file_groups_is_gated() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted_with_file_groups(&schema, sort_exprs.clone(), 4);
+        let physical_plan = sort_exec(sort_exprs, source, false);
+        let rule = ReplaceWithOrderPreservingVariants::new(false, true);
+        let decide = |rule: &ReplaceWithOrderPreservingVariants,
+                      config: &ConfigOptions|
+         -> Result<SortOutcome> {
+            let (optimized, decisions) =
+                rule.optimize_and_record_decisions(Arc::clone(&physical_plan), config)?;
+            assert_eq!(decisions.len(), 1);
+            let outcome = decisions[0].outcome.clone();
+            if matches!(outcome, SortOutcome::Kept(_)) {
+                assert!(Arc::ptr_eq(&optimized, &physical_plan));
+            }
+            Ok(outcome)
+        };
+
+        // By default, the sorted partitions are merged:
+        assert_eq!(
+            decide(&rule, &ConfigOptions::new())?,
+            SortOutcome::Removed(SortRemovalReason::MergedSortedPartitions)
+        );
+
+        // The merge is declined like the order-preserving variant of a
+        // `CoalescePartitionsExec`:
+        let mut config = ConfigOptions::new();
+        config.optimizer.enable_order_preserving_coalesce = false;
+        assert_eq!(
+            decide(&rule, &config)?,
+            SortOutcome::Kept(SortKeptReason::MergeDisabled)
+        );
+        let vetoing_rule = rule
+            .clone()
+            .with_replacement_filter(|plan| !plan.as_any().is::<SortExec>());
+        assert_eq!(
+            decide(&vetoing_rule, &ConfigOptions::new())?,
+            SortOutcome::Kept(SortKeptReason::ReplacementRejected)
+        );
+        let mut config = ConfigOptions::new();
+        config.optimizer.order_preserving_parallelism_bias = 0.9;
+        assert_eq!(
+            decide(&rule, &config)?,
+            SortOutcome::Kept(SortKeptReason::ParallelismBias)
+        );
+        let mut config = ConfigOptions::new();
+        config.optimizer.max_order_preserving_merge_fanin = Some(2);
+        assert_eq!(
+            decide(&rule, &config)?,
+            SortOutcome::Kept(SortKeptReason::BlockedByFaninCap {
+                partitions: 4,
+                cap: 2
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_of_small_sorted_partitions_is_more_expensive() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(vec![1]))],
+        )?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        // Each partition holds a single row, so merging them costs more than
+        // sorting all of them:
+        let source = Arc::new(
+            MemoryExec::try_new(&vec![vec![batch]; 4], Arc::clone(&schema), None)?
+                .with_sort_information(vec![sort_exprs.clone()]),
+        );
+        let physical_plan = sort_exec(sort_exprs, source, false);
 
-        // Expected bounded results with and without flag
-        let expected_optimized_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      CoalesceBatchesExec: target_batch_size=8192",
-            "        FilterExec: c@1 > 3",
-            "          RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "            RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "              CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
-        let expected_optimized_bounded_sort_preserve = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "    CoalesceBatchesExec: target_batch_size=8192",
-            "      FilterExec: c@1 > 3",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
-        assert_optimized_in_all_boundedness_situations!(
-            expected_input_unbounded,
-            expected_input_bounded,
-            expected_optimized_unbounded,
-            expected_optimized_bounded,
-            expected_optimized_bounded_sort_preserve,
-            physical_plan,
-            source_unbounded
+        let (optimized, decisions) = ReplaceWithOrderPreservingVariants::new(false, true)
+            .optimize_and_record_decisions(
+                Arc::clone(&physical_plan),
+                &ConfigOptions::new(),
+            )?;
+        assert_eq!(get_plan_string(&optimized), get_plan_string(&physical_plan));
+        assert_eq!(
+            decisions[0].outcome,
+            SortOutcome::Kept(SortKeptReason::MergingMoreExpensive)
         );
         Ok(())
     }
 
     #[rstest]
     #[tokio::test]
-    async fn test_not_replace_with_different_orderings(
+    async fn test_replacement_is_idempotent(
         #[values(false, true)] source_unbounded: bool,
+        #[values(false, true)] prefer_existing_sort: bool,
     ) -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
         let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
+            stream_exec_ordered(&schema, sort_exprs.clone())
         } else {
-            csv_exec_sorted(&schema, sort_exprs)
+            csv_exec_sorted(&schema, sort_exprs.clone())
         };
         let repartition_rr = repartition_exec_round_robin(source);
         let repartition_hash = repartition_exec_hash(repartition_rr);
-        let sort = sort_exec(
-            vec![sort_expr_default("c", &repartition_hash.schema())],
-            repartition_hash,
-            true,
-        );
+        let filter = filter_exec(repartition_hash);
+        let sort = sort_exec(sort_exprs.clone(), filter, true);
+        let merge = sort_preserving_merge_exec(sort_exprs.clone(), sort);
+        let repartition_hash = repartition_exec_hash(merge);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
 
-        let physical_plan = sort_preserving_merge_exec(
-            vec![sort_expr_default("c", &sort.schema())],
-            sort,
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = prefer_existing_sort;
+        let run = |plan| {
+            OrderPreservationContext::new_default(plan).transform_up(|opc| {
+                replace_with_order_preserving_variants(opc, false, false, &config)
+            })
+        };
+        let optimized = run(physical_plan)?.data.plan;
+        // Running the rule on its own output changes nothing:
+        let rerun = run(Arc::clone(&optimized))?;
+        assert!(!rerun.transformed);
+        assert_eq!(
+            get_plan_string(&rerun.data.plan),
+            get_plan_string(&optimized)
         );
+        Ok(())
+    }
 
-        // Expected inputs unbounded and bounded
-        let expected_input_unbounded = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
-        ];
-        let expected_input_bounded = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
+    #[tokio::test]
+    async fn test_with_replacement_filter() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let union = Arc::new(UnionExec::new(vec![source.clone(), source]));
+        let coalesce_partitions = coalesce_partitions_exec(union);
+        let physical_plan = sort_exec(sort_exprs.clone(), coalesce_partitions, false);
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let other_plan = sort_exec(sort_exprs, coalesce_partitions, false);
 
-        // Expected unbounded result (same for with and without flag)
-        let expected_optimized_unbounded = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+        // Only allow replacing `CoalescePartitionsExec`s:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false)
+            .with_replacement_filter(|plan| !plan.as_any().is::<RepartitionExec>());
+
+        let optimized = rule.optimize(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  UnionExec",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
 
-        // Expected bounded results same with and without flag, because ordering requirement of the executor is different than the existing ordering.
-        let expected_optimized_bounded = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
+        // The sort is kept, as the repartition below it can not be replaced:
+        let optimized = rule.optimize(other_plan, &config)?;
+        let expected_optimized = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
             "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
             "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
             "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-        let expected_optimized_bounded_sort_preserve = expected_optimized_bounded;
-
-        assert_optimized_in_all_boundedness_situations!(
-            expected_input_unbounded,
-            expected_input_bounded,
-            expected_optimized_unbounded,
-            expected_optimized_bounded,
-            expected_optimized_bounded_sort_preserve,
-            physical_plan,
-            source_unbounded
-        );
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
         Ok(())
     }
 
     #[rstest]
     #[tokio::test]
-    async fn test_with_lost_ordering(
+    async fn test_with_global_limit_between_sort_and_repartition(
         #[values(false, true)] source_unbounded: bool,
     ) -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
         let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
+            stream_exec_ordered(&schema, sort_exprs.clone())
         } else {
-            csv_exec_sorted(&schema, sort_exprs)
+            csv_exec_sorted(&schema, sort_exprs.clone())
         };
         let repartition_rr = repartition_exec_round_robin(source);
         let repartition_hash = repartition_exec_hash(repartition_rr);
         let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
-        let physical_plan =
-            sort_exec(vec![sort_expr("a", &schema)], coalesce_partitions, false);
+        let limit = Arc::new(GlobalLimitExec::new(coalesce_partitions, 5, Some(10)));
+        let physical_plan = sort_exec(sort_exprs, limit, false);
 
-        // Expected inputs unbounded and bounded
-        let expected_input_unbounded = [
-            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
-            "  CoalescePartitionsExec",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
-        ];
-        let expected_input_bounded = [
-            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
-            "  CoalescePartitionsExec",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
-
-        // Expected unbounded result (same for with and without flag)
-        let expected_optimized_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
-        ];
-
-        // Expected bounded results with and without flag
-        let expected_optimized_bounded = [
-            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
-            "  CoalescePartitionsExec",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
-        let expected_optimized_bounded_sort_preserve = [
-            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
-            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
-            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-        ];
-        assert_optimized_in_all_boundedness_situations!(
-            expected_input_unbounded,
-            expected_input_bounded,
-            expected_optimized_unbounded,
-            expected_optimized_bounded,
-            expected_optimized_bounded_sort_preserve,
-            physical_plan,
-            source_unbounded
+        let expected_input = if source_unbounded {
+            vec![
+                "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+                "  GlobalLimitExec: skip=5, fetch=10",
+                "    CoalescePartitionsExec",
+                "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+                "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            ]
+        } else {
+            vec![
+                "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+                "  GlobalLimitExec: skip=5, fetch=10",
+                "    CoalescePartitionsExec",
+                "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+                "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        };
+        // The limit stays above the merge, so it still skips and fetches rows
+        // of a single partition. As the limit bounds its input, the sort does
+        // not break the pipeline even for an unbounded source, so the sort is
+        // only removed with `prefer_existing_sort`:
+        let expected_optimized = if source_unbounded {
+            vec![
+                "GlobalLimitExec: skip=5, fetch=10",
+                "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            ]
+        } else {
+            vec![
+                "GlobalLimitExec: skip=5, fetch=10",
+                "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        };
+        assert_optimized_prefer_sort_on_off!(
+            expected_input,
+            expected_input,
+            expected_optimized,
+            physical_plan
         );
         Ok(())
     }
 
     #[rstest]
     #[tokio::test]
-    async fn test_with_lost_and_kept_ordering(
+    async fn test_with_local_limit_between_sort_and_repartition(
         #[values(false, true)] source_unbounded: bool,
     ) -> Result<()> {
         let schema = create_test_schema()?;
         let sort_exprs = vec![sort_expr("a", &schema)];
         let source = if source_unbounded {
-            stream_exec_ordered(&schema, sort_exprs)
+            stream_exec_ordered(&schema, sort_exprs.clone())
         } else {
-            csv_exec_sorted(&schema, sort_exprs)
+            csv_exec_sorted(&schema, sort_exprs.clone())
         };
         let repartition_rr = repartition_exec_round_robin(source);
         let repartition_hash = repartition_exec_hash(repartition_rr);
-        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
-        let sort = sort_exec(
-            vec![sort_expr_default("c", &coalesce_partitions.schema())],
-            coalesce_partitions,
-            false,
-        );
-        let repartition_rr2 = repartition_exec_round_robin(sort);
-        let repartition_hash2 = repartition_exec_hash(repartition_rr2);
-        let filter = filter_exec(repartition_hash2);
-        let sort2 =
-            sort_exec(vec![sort_expr_default("c", &filter.schema())], filter, true);
+        let limit = Arc::new(LocalLimitExec::new(repartition_hash, 10));
+        let sort = sort_exec(sort_exprs.clone(), limit, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
 
-        let physical_plan = sort_preserving_merge_exec(
-            vec![sort_expr_default("c", &sort2.schema())],
-            sort2,
+        let expected_input = if source_unbounded {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+                "    LocalLimitExec: fetch=10",
+                "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+                "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            ]
+        } else {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+                "    LocalLimitExec: fetch=10",
+                "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+                "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        };
+        // The limit still applies to each partition of the repartition. As
+        // the limit bounds its input, the sort does not break the pipeline
+        // even for an unbounded source, so the sort is only removed with
+        // `prefer_existing_sort`:
+        let expected_optimized = if source_unbounded {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  LocalLimitExec: fetch=10",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            ]
+        } else {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  LocalLimitExec: fetch=10",
+                "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        };
+        assert_optimized_prefer_sort_on_off!(
+            expected_input,
+            expected_input,
+            expected_optimized,
+            physical_plan
         );
+        Ok(())
+    }
 
-        // Expected inputs unbounded and bounded
-        let expected_input_unbounded = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+    #[tokio::test]
+    async fn test_annotate_order_preserving_variants() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let merge = sort_preserving_merge_exec(sort_exprs.clone(), sort);
+        let repartition_hash = repartition_exec_hash(merge);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+
+        // Only the operators introduced by the rule are annotated; neither the
+        // merge that was already in the plan nor the repartition above it,
+        // which keeps the ordering of its single input partition as it is:
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.annotate_order_preserving_variants = true;
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        let expected_optimized = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST] (order-preserving-opt)",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=1",
+            "    SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST (order-preserving-opt)",
             "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
-            "            CoalescePartitionsExec",
-            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "                  StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_repartition_over_coalesce_partitions(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            let source = stream_exec_ordered(&schema, sort_exprs.clone());
+            Arc::new(UnionExec::new(vec![source.clone(), source])) as _
+        } else {
+            csv_exec_sorted_with_file_groups(&schema, sort_exprs.clone(), 2)
+        };
+        let coalesce_partitions = coalesce_partitions_exec(source);
+        let repartition_hash = repartition_exec_hash(coalesce_partitions);
+        let sort = sort_exec(sort_exprs.clone(), repartition_hash, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+
+        let expected_input_unbounded = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=1",
+            "      CoalescePartitionsExec",
+            "        UnionExec",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
         let expected_input_bounded = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
-            "            CoalescePartitionsExec",
-            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "                  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=1",
+            "      CoalescePartitionsExec",
+            "        CsvExec: file_groups={2 groups: [[file_path_0], [file_path_1]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
 
-        // Expected unbounded result (same for with and without flag)
+        // The coalesce and the repartition are fused into a single
+        // order-preserving repartition:
         let expected_optimized_unbounded = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  FilterExec: c@1 > 3",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=c@1 ASC",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
-            "          CoalescePartitionsExec",
-            "            RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "              RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "                StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
-        ];
-
-        // Expected bounded results with and without flag
-        let expected_optimized_bounded = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  SortExec: expr=[c@1 ASC], preserve_partitioning=[true]",
-            "    FilterExec: c@1 > 3",
-            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "          SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
-            "            CoalescePartitionsExec",
-            "              RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "                RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "                  CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=2, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    UnionExec",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "      StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
+        let expected_optimized_bounded = expected_input_bounded;
         let expected_optimized_bounded_sort_preserve = [
-            "SortPreservingMergeExec: [c@1 ASC]",
-            "  FilterExec: c@1 > 3",
-            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=c@1 ASC",
-            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "        SortExec: expr=[c@1 ASC], preserve_partitioning=[false]",
-            "          CoalescePartitionsExec",
-            "            RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "              RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "                CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=2, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "    CsvExec: file_groups={2 groups: [[file_path_0], [file_path_1]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
         assert_optimized_in_all_boundedness_situations!(
             expected_input_unbounded,
@@ -1227,106 +8877,98 @@ mod tests {
 
     #[rstest]
     #[tokio::test]
-    async fn test_with_multiple_child_trees(
-        #[values(false, true)] source_unbounded: bool,
+    async fn test_transformed_only_if_plan_changes(
+        #[values(false, true)] prefer_existing_sort: bool,
     ) -> Result<()> {
         let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let removable_sort = sort_exec(sort_exprs.clone(), coalesce_partitions, false);
+        let source = csv_exec_sorted(&schema, vec![]);
+        let repartition_rr = repartition_exec_round_robin(source);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_rr);
+        let unremovable_sort = sort_exec(sort_exprs, coalesce_partitions, false);
 
-        let left_sort_exprs = vec![sort_expr("a", &schema)];
-        let left_source = if source_unbounded {
-            stream_exec_ordered(&schema, left_sort_exprs)
-        } else {
-            csv_exec_sorted(&schema, left_sort_exprs)
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = prefer_existing_sort;
+        let run = |plan| {
+            OrderPreservationContext::new_default(plan).transform_up(|opc| {
+                replace_with_order_preserving_variants(opc, false, false, &config)
+            })
         };
-        let left_repartition_rr = repartition_exec_round_robin(left_source);
-        let left_repartition_hash = repartition_exec_hash(left_repartition_rr);
-        let left_coalesce_partitions =
-            Arc::new(CoalesceBatchesExec::new(left_repartition_hash, 4096));
+        // The removable sort is only removed with `prefer_existing_sort`:
+        let result = run(Arc::clone(&removable_sort))?;
+        assert_eq!(result.transformed, prefer_existing_sort);
+        assert_eq!(is_sort(&result.data.plan), !prefer_existing_sort);
+        let result = run(Arc::clone(&unremovable_sort))?;
+        assert!(!result.transformed);
+        assert!(Arc::ptr_eq(&result.data.plan, &unremovable_sort));
+        // Revisiting the sort for a sink requiring its ordering changes nothing
+        // either:
+        let sort_order = Some(PhysicalSortRequirement::from_sort_exprs(
+            unremovable_sort.output_ordering().unwrap(),
+        ));
+        let sink = data_sink_exec(Arc::clone(&unremovable_sort), sort_order);
+        let result = run(Arc::clone(&sink))?;
+        assert!(!result.transformed);
+        assert!(Arc::ptr_eq(&result.data.plan, &sink));
+        Ok(())
+    }
 
-        let right_sort_exprs = vec![sort_expr("a", &schema)];
-        let right_source = if source_unbounded {
-            stream_exec_ordered(&schema, right_sort_exprs)
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_coalesce_batches_with_fetch(
+        #[values(false, true)] source_unbounded: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = if source_unbounded {
+            stream_exec_ordered(&schema, sort_exprs.clone())
         } else {
-            csv_exec_sorted(&schema, right_sort_exprs)
+            csv_exec_sorted(&schema, sort_exprs.clone())
         };
-        let right_repartition_rr = repartition_exec_round_robin(right_source);
-        let right_repartition_hash = repartition_exec_hash(right_repartition_rr);
-        let right_coalesce_partitions =
-            Arc::new(CoalesceBatchesExec::new(right_repartition_hash, 4096));
-
-        let hash_join_exec =
-            hash_join_exec(left_coalesce_partitions, right_coalesce_partitions);
-        let sort = sort_exec(
-            vec![sort_expr_default("a", &hash_join_exec.schema())],
-            hash_join_exec,
-            true,
-        );
-
-        let physical_plan = sort_preserving_merge_exec(
-            vec![sort_expr_default("a", &sort.schema())],
-            sort,
+        let repartition_rr = repartition_exec_round_robin(source);
+        let repartition_hash = repartition_exec_hash(repartition_rr);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_hash);
+        let coalesce_batches = Arc::new(
+            CoalesceBatchesExec::new(coalesce_partitions, 8192).with_fetch(Some(10)),
         );
+        let physical_plan = sort_exec(sort_exprs, coalesce_batches, false);
 
-        // Expected inputs unbounded and bounded
         let expected_input_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
-            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
-            "      CoalesceBatchesExec: target_batch_size=4096",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
-            "      CoalesceBatchesExec: target_batch_size=4096",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalesceBatchesExec: target_batch_size=8192, fetch=10",
+            "    CoalescePartitionsExec",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
         let expected_input_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
-            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
-            "      CoalesceBatchesExec: target_batch_size=4096",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-            "      CoalesceBatchesExec: target_batch_size=4096",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalesceBatchesExec: target_batch_size=8192, fetch=10",
+            "    CoalescePartitionsExec",
+            "      RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "          CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-
-        // Expected unbounded result (same for with and without flag)
         let expected_optimized_unbounded = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
-            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
-            "      CoalesceBatchesExec: target_batch_size=4096",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
-            "      CoalesceBatchesExec: target_batch_size=4096",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
+            "CoalesceBatchesExec: target_batch_size=8192, fetch=10",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=10",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        StreamingTableExec: partition_sizes=1, projection=[a, c, d], infinite_source=true, output_ordering=[a@0 ASC NULLS LAST]",
         ];
-
-        // Expected bounded results same with and without flag, because ordering get lost during intermediate executor anyway. Hence no need to preserve
-        // existing ordering.
-        let expected_optimized_bounded = [
-            "SortPreservingMergeExec: [a@0 ASC]",
-            "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
-            "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(c@1, c@1)]",
-            "      CoalesceBatchesExec: target_batch_size=4096",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
-            "      CoalesceBatchesExec: target_batch_size=4096",
-            "        RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
-            "          RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
-            "            CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        let expected_optimized_bounded = expected_input_bounded;
+        let expected_optimized_bounded_sort_preserve = [
+            "CoalesceBatchesExec: target_batch_size=8192, fetch=10",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST], fetch=10",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
         ];
-        let expected_optimized_bounded_sort_preserve = expected_optimized_bounded;
-
         assert_optimized_in_all_boundedness_situations!(
             expected_input_unbounded,
             expected_input_bounded,
@@ -1339,9 +8981,230 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_downgrade_unnecessary_order_preserving_merges() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let spm = sort_preserving_merge_exec(sort_exprs.clone(), repartition_rr);
+
+        // Nothing consumes the ordering of the top-level merge:
+        let result = downgrade_unnecessary_order_preserving_merges(Arc::clone(&spm))?;
+        let expected_downgraded = [
+            "CoalescePartitionsExec",
+            "  RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert!(result.transformed);
+        assert_eq!(get_plan_string(&result.data), expected_downgraded);
+
+        // A sink requiring the ordering keeps the merge:
+        let sort_order = PhysicalSortRequirement::from_sort_exprs(&sort_exprs);
+        let physical_plan = data_sink_exec(spm, Some(sort_order));
+        let result =
+            downgrade_unnecessary_order_preserving_merges(Arc::clone(&physical_plan))?;
+        let expected_kept = [
+            "DataSinkExec: sink=TestSink",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert!(!result.transformed);
+        assert_eq!(get_plan_string(&result.data), expected_kept);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ordering_connections_with_divergent_sibling_orderings() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sorted_branch = |column| {
+            let source = csv_exec_sorted(&schema, vec![sort_expr(column, &schema)]);
+            let ordering = vec![sort_expr(column, &source.schema())];
+            let repartition = repartition_exec_round_robin(source);
+            let coalesce_partitions = coalesce_partitions_exec(repartition);
+            (
+                sort_exec(ordering.clone(), coalesce_partitions, false),
+                ordering,
+            )
+        };
+        let (sort_a, ordering_a) = sorted_branch("a");
+        let (sort_c, ordering_c) = sorted_branch("c");
+        let physical_plan: Arc<dyn ExecutionPlan> =
+            Arc::new(UnionExec::new(vec![sort_a, sort_c]));
+
+        // The input of each sort carries the ordering of its own source:
+        let mut orderings = vec![];
+        OrderPreservationContext::new_default(Arc::clone(&physical_plan)).transform_up(
+            |mut opc| {
+                update_children(&mut opc);
+                if is_sort(&opc.plan) {
+                    orderings.push(opc.children[0].data.clone());
+                }
+                Ok(Transformed::no(opc))
+            },
+        )?;
+        assert_eq!(orderings, vec![Some(ordering_a), Some(ordering_c)]);
+
+        let expected_input = [
+            "UnionExec",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "    CoalescePartitionsExec",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  SortExec: expr=[c@1 ASC NULLS LAST], preserve_partitioning=[false]",
+            "    CoalescePartitionsExec",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[c@1 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+
+        // Each sort is replaced with a merge on its own ordering:
+        let config = SessionConfig::new().with_prefer_existing_sort(true);
+        let optimized = optimize_with_config(physical_plan, config.options())?;
+        let expected_optimized = [
+            "UnionExec",
+            "  SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            "  SortPreservingMergeExec: [c@1 ASC NULLS LAST]",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[c@1 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_enable_order_preserving_repartition_and_coalesce(
+        #[values(false, true)] enable_repartition: bool,
+        #[values(false, true)] enable_coalesce: bool,
+    ) -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        config.optimizer.enable_order_preserving_repartition = enable_repartition;
+        config.optimizer.enable_order_preserving_coalesce = enable_coalesce;
+
+        // Removing this sort only needs the coalesce to be replaced:
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_rr);
+        let physical_plan = sort_exec(sort_exprs.clone(), coalesce_partitions, false);
+        let expected_input = [
+            "SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[false]",
+            "  CoalescePartitionsExec",
+            "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized = if enable_coalesce {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+        // Removing this sort only needs the hash repartition to be replaced:
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition = repartition_exec_hash(repartition_exec_round_robin(source));
+        let sort = sort_exec(sort_exprs.clone(), repartition, true);
+        let physical_plan = sort_preserving_merge_exec(sort_exprs, sort);
+        let expected_input = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  SortExec: expr=[a@0 ASC NULLS LAST], preserve_partitioning=[true]",
+            "    RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8",
+            "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "        CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        let expected_optimized = if enable_repartition {
+            vec![
+                "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+                "  RepartitionExec: partitioning=Hash([c@1], 8), input_partitions=8, preserve_order=true, sort_exprs=a@0 ASC NULLS LAST",
+                "    RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+                "      CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+            ]
+        } else {
+            expected_input.to_vec()
+        };
+        assert_eq!(get_plan_string(&physical_plan), expected_input);
+        let optimized = optimize_with_config(physical_plan, &config)?;
+        assert_eq!(get_plan_string(&optimized), expected_optimized);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deeply_shared_subplan_is_optimized_once() -> Result<()> {
+        let schema = create_test_schema()?;
+        let sort_exprs = vec![sort_expr("a", &schema)];
+        let source = csv_exec_sorted(&schema, sort_exprs.clone());
+        let repartition_rr = repartition_exec_round_robin(source);
+        let coalesce_partitions = coalesce_partitions_exec(repartition_rr);
+        let mut physical_plan = sort_exec(sort_exprs, coalesce_partitions, false);
+        // Every level reads the level below it twice, so there are 2^12 paths
+        // from the root to the shared sort:
+        for _ in 0..12 {
+            physical_plan = Arc::new(UnionExec::new(vec![
+                Arc::clone(&physical_plan),
+                physical_plan,
+            ]));
+        }
+
+        // The replacement filter is consulted whenever the alternative of the
+        // shared sort is computed:
+        let replacement_checks = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&replacement_checks);
+        let rule = ReplaceWithOrderPreservingVariants::new(false, false)
+            .with_replacement_filter(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                true
+            });
+        let mut config = ConfigOptions::new();
+        config.optimizer.prefer_existing_sort = true;
+        let optimized = rule.optimize(physical_plan, &config)?;
+        assert_eq!(replacement_checks.load(Ordering::Relaxed), 1);
+
+        // The optimized plan still shares a single copy of the subplan:
+        let mut shared = optimized;
+        while shared.as_any().is::<UnionExec>() {
+            let children = shared.children();
+            assert!(Arc::ptr_eq(children[0], children[1]));
+            shared = Arc::clone(children[0]);
+        }
+        let expected_shared = [
+            "SortPreservingMergeExec: [a@0 ASC NULLS LAST]",
+            "  RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+            "    CsvExec: file_groups={1 group: [[file_path]]}, projection=[a, c, d], output_ordering=[a@0 ASC NULLS LAST], has_header=true",
+        ];
+        assert_eq!(get_plan_string(&shared), expected_shared);
+        Ok(())
+    }
+
     // End test cases
     // Start test helpers
 
+    /// Runs the `replace_with_order_preserving_variants` sub-rule on the given
+    /// plan with the given configuration.
+    fn optimize_with_config(
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        OrderPreservationContext::new_default(plan)
+            .transform_up(|ctx| {
+                replace_with_order_preserving_variants(ctx, false, false, config)
+            })
+            .data()
+            .and_then(check_integrity)
+            .map(|ctx| ctx.plan)
+    }
+
     fn sort_expr(name: &str, schema: &Schema) -> PhysicalSortExpr {
         let sort_opts = SortOptions {
             nulls_first: false,
@@ -1448,6 +9311,139 @@ mod tests {
         )
     }
 
+    /// Test hints that report every [`CsvExec`] as expensive to re-scan.
+    #[derive(Debug)]
+    struct ExpensiveCsvRescanHints;
+
+    impl OrderPreservingHints for ExpensiveCsvRescanHints {
+        fn is_rescan_expensive(&self, plan: &dyn ExecutionPlan) -> bool {
+            plan.as_any().is::<CsvExec>()
+        }
+    }
+
+    /// Test hints that expose the nested plan of every [`PassThroughTestExec`].
+    #[derive(Debug)]
+    struct NestedPlanHints;
+
+    impl OrderPreservingHints for NestedPlanHints {
+        fn nested_plans<'a>(
+            &self,
+            plan: &'a dyn ExecutionPlan,
+        ) -> Vec<&'a Arc<dyn ExecutionPlan>> {
+            match plan.as_any().downcast_ref::<PassThroughTestExec>() {
+                Some(exec) => exec.nested_plan().into_iter().collect(),
+                None => vec![],
+            }
+        }
+
+        fn with_new_nested_plans(
+            &self,
+            plan: Arc<dyn ExecutionPlan>,
+            mut nested_plans: Vec<Arc<dyn ExecutionPlan>>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            let Some(exec) = plan.as_any().downcast_ref::<PassThroughTestExec>() else {
+                return internal_err!("{} does not have nested plans", plan.name());
+            };
+            Ok(exec
+                .clone()
+                .with_nested_plan(nested_plans.swap_remove(0))
+                .into_arc())
+        }
+    }
+
+    /// Test hints that report every [`CoalesceBatchesExec`] as routing rows to
+    /// its partitions non-deterministically.
+    #[derive(Debug)]
+    struct NonDeterministicCoalesceBatchesHints;
+
+    impl OrderPreservingHints for NonDeterministicCoalesceBatchesHints {
+        fn has_deterministic_partitioning(&self, plan: &dyn ExecutionPlan) -> bool {
+            !plan.as_any().is::<CoalesceBatchesExec>()
+        }
+    }
+
+    /// Creates a [`MemoryExec`] with the given number of empty partitions,
+    /// each sorted on the given ordering.
+    fn memory_exec_sorted(
+        schema: &SchemaRef,
+        ordering: Vec<PhysicalSortExpr>,
+        partitions: usize,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let source =
+            MemoryExec::try_new(&vec![vec![]; partitions], Arc::clone(schema), None)?
+                .with_sort_information(vec![ordering]);
+        Ok(Arc::new(source))
+    }
+
+    /// Test hints that report every [`MemoryExec`] as able to scan its
+    /// partitions backwards, which reverses their ordering.
+    #[derive(Debug)]
+    struct ReversibleMemoryHints;
+
+    impl OrderPreservingHints for ReversibleMemoryHints {
+        fn can_reverse_scan(&self, plan: &dyn ExecutionPlan) -> bool {
+            plan.as_any().is::<MemoryExec>()
+        }
+
+        fn with_reversed_scan(
+            &self,
+            plan: &dyn ExecutionPlan,
+        ) -> Option<Arc<dyn ExecutionPlan>> {
+            let memory = plan.as_any().downcast_ref::<MemoryExec>()?;
+            let ordering = reverse_order_bys(memory.properties().output_ordering()?);
+            let reversed = MemoryExec::try_new(
+                memory.partitions(),
+                memory.schema(),
+                memory.projection().clone(),
+            )
+            .ok()?
+            .with_sort_information(vec![ordering]);
+            Some(Arc::new(reversed))
+        }
+    }
+
+    fn data_sink_exec(
+        input: Arc<dyn ExecutionPlan>,
+        sort_order: Option<LexRequirement>,
+    ) -> Arc<dyn ExecutionPlan> {
+        let schema = input.schema();
+        Arc::new(DataSinkExec::new(
+            input,
+            Arc::new(TestSink),
+            schema,
+            sort_order,
+        ))
+    }
+
+    /// A test [`DataSink`] that discards its input.
+    #[derive(Debug)]
+    struct TestSink;
+
+    impl DisplayAs for TestSink {
+        fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+            write!(f, "TestSink")
+        }
+    }
+
+    #[async_trait]
+    impl DataSink for TestSink {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn metrics(&self) -> Option<MetricsSet> {
+            None
+        }
+
+        async fn write_all(
+            &self,
+            _data: SendableRecordBatchStream,
+            _context: &Arc<TaskContext>,
+        ) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
     fn create_test_schema() -> Result<SchemaRef> {
         let column_a = Field::new("a", DataType::Int32, false);
         let column_b = Field::new("b", DataType::Int32, false);
@@ -1510,4 +9506,38 @@ mod tests {
             .build(),
         )
     }
+
+    // creates a csv exec source with the given number of file groups, each
+    // of them sorted by the given expressions, for the test purposes
+    fn csv_exec_sorted_with_file_groups(
+        schema: &SchemaRef,
+        sort_exprs: impl IntoIterator<Item = PhysicalSortExpr>,
+        file_groups: usize,
+    ) -> Arc<dyn ExecutionPlan> {
+        let sort_exprs = sort_exprs.into_iter().collect();
+        let projection: Vec<usize> = vec![0, 2, 3];
+        let file_groups = (0..file_groups)
+            .map(|i| vec![PartitionedFile::new(format!("file_path_{i}"), 100)])
+            .collect();
+
+        Arc::new(
+            CsvExec::builder(
+                FileScanConfig::new(
+                    ObjectStoreUrl::parse("test:///").unwrap(),
+                    schema.clone(),
+                )
+                .with_file_groups(file_groups)
+                .with_projection(Some(projection))
+                .with_output_ordering(vec![sort_exprs]),
+            )
+            .with_has_header(true)
+            .with_delimeter(0)
+            .with_quote(b'"')
+            .with_escape(None)
+            .with_comment(None)
+            .with_newlines_in_values(false)
+            .with_file_compression_type(FileCompressionType::UNCOMPRESSED)
+            .build(),
+        )
+    }
 }
@@ -19,6 +19,7 @@
 
 use std::any::Any;
 use std::fmt::Formatter;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use crate::datasource::listing::PartitionedFile;
@@ -43,7 +44,7 @@ use crate::prelude::{CsvReadOptions, SessionContext};
 
 use arrow_schema::{Schema, SchemaRef, SortOptions};
 use datafusion_common::tree_node::{Transformed, TransformedResult, TreeNode};
-use datafusion_common::JoinType;
+use datafusion_common::{internal_err, JoinType, Statistics};
 use datafusion_execution::object_store::ObjectStoreUrl;
 use datafusion_expr::{WindowFrame, WindowFunctionDefinition};
 use datafusion_functions_aggregate::count::count_udaf;
@@ -51,7 +52,7 @@ use datafusion_physical_expr::expressions::col;
 use datafusion_physical_expr::{PhysicalExpr, PhysicalSortExpr};
 use datafusion_physical_plan::tree_node::PlanContext;
 use datafusion_physical_plan::{
-    displayable, DisplayAs, DisplayFormatType, PlanProperties,
+    displayable, DisplayAs, DisplayFormatType, ExecutionPlanProperties, PlanProperties,
 };
 
 use async_trait::async_trait;
@@ -452,6 +453,123 @@ impl ExecutionPlan for RequirementsTestExec {
     }
 }
 
+/// An order-maintaining test [`ExecutionPlan`] that passes its input through,
+/// and whose behavior can be configured.
+#[derive(Debug, Clone)]
+pub struct PassThroughTestExec {
+    input: Arc<dyn ExecutionPlan>,
+    nested_plan: Option<Arc<dyn ExecutionPlan>>,
+    statistics: Option<Statistics>,
+    children_calls: Option<Arc<AtomicUsize>>,
+    reject_new_children: bool,
+}
+
+impl PassThroughTestExec {
+    /// creates a node that passes the given input through
+    pub fn new(input: Arc<dyn ExecutionPlan>) -> Self {
+        Self {
+            input,
+            nested_plan: None,
+            statistics: None,
+            children_calls: None,
+            reject_new_children: false,
+        }
+    }
+
+    /// sets a plan (e.g. a subquery) that this node evaluates on its own
+    pub fn with_nested_plan(mut self, nested_plan: Arc<dyn ExecutionPlan>) -> Self {
+        self.nested_plan = Some(nested_plan);
+        self
+    }
+
+    /// returns the plan set by [`Self::with_nested_plan`], if any
+    pub fn nested_plan(&self) -> Option<&Arc<dyn ExecutionPlan>> {
+        self.nested_plan.as_ref()
+    }
+
+    /// sets the statistics reported instead of the ones of the input
+    pub fn with_statistics(mut self, statistics: Statistics) -> Self {
+        self.statistics = Some(statistics);
+        self
+    }
+
+    /// sets a counter that is incremented whenever the children are accessed
+    pub fn with_children_calls(mut self, children_calls: Arc<AtomicUsize>) -> Self {
+        self.children_calls = Some(children_calls);
+        self
+    }
+
+    /// set the flag to fail whenever the input is replaced
+    pub fn with_reject_new_children(mut self, reject_new_children: bool) -> Self {
+        self.reject_new_children = reject_new_children;
+        self
+    }
+
+    /// returns this ExecutionPlan as an Arc<dyn ExecutionPlan>
+    pub fn into_arc(self) -> Arc<dyn ExecutionPlan> {
+        Arc::new(self)
+    }
+}
+
+impl DisplayAs for PassThroughTestExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "PassThroughTestExec")
+    }
+}
+
+impl ExecutionPlan for PassThroughTestExec {
+    fn name(&self) -> &str {
+        "PassThroughTestExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        self.input.properties()
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        if let Some(children_calls) = &self.children_calls {
+            children_calls.fetch_add(1, Ordering::Relaxed);
+        }
+        vec![&self.input]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if self.reject_new_children {
+            return internal_err!("PassThroughTestExec does not accept new children");
+        }
+        assert_eq!(children.len(), 1);
+        let mut exec = self.as_ref().clone();
+        exec.input = Arc::clone(&children[0]);
+        Ok(exec.into_arc())
+    }
+
+    fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        unimplemented!("Test exec does not support execution")
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        match &self.statistics {
+            Some(statistics) => Ok(statistics.clone()),
+            None => self.input.statistics(),
+        }
+    }
+}
+
 /// A [`PlanContext`] object is susceptible to being left in an inconsistent state after
 /// untested mutable operations. It is crucial that there be no discrepancies between a plan
 /// associated with the root node and the plan generated after traversing all nodes
@@ -477,3 +595,32 @@ pub fn check_integrity<T: Clone>(context: PlanContext<T>) -> Result<PlanContext<
         })
         .data()
 }
+
+/// Compares two plans structurally: the operator types, output orderings and
+/// output partitionings must match at every level of the tree. Incidental
+/// fields such as metrics, batch sizes or file paths are ignored.
+pub fn plans_structurally_equal(
+    left: &Arc<dyn ExecutionPlan>,
+    right: &Arc<dyn ExecutionPlan>,
+) -> bool {
+    let same_partitioning =
+        match (left.output_partitioning(), right.output_partitioning()) {
+            // `Partitioning::UnknownPartitioning` never compares equal to itself:
+            (
+                Partitioning::UnknownPartitioning(left_count),
+                Partitioning::UnknownPartitioning(right_count),
+            ) => left_count == right_count,
+            (left_partitioning, right_partitioning) => {
+                left_partitioning == right_partitioning
+            }
+        };
+    let left_children = left.children();
+    let right_children = right.children();
+    left.name() == right.name()
+        && left.output_ordering() == right.output_ordering()
+        && same_partitioning
+        && left_children.len() == right_children.len()
+        && left_children.into_iter().zip(right_children).all(
+            |(left_child, right_child)| plans_structurally_equal(left_child, right_child),
+        )
+}
@@ -62,6 +62,9 @@ pub struct CrossJoinExec {
     left_fut: OnceAsync<JoinLeftData>,
     /// Execution plan metrics
     metrics: ExecutionPlanMetricsSet,
+    /// Whether the left side consists of exactly one row, in which case the
+    /// ordering of the right side is maintained
+    left_is_single_row: bool,
     cache: PlanProperties,
 }
 
@@ -78,13 +81,22 @@ impl CrossJoinExec {
         };
 
         let schema = Arc::new(Schema::new(all_columns));
-        let cache = Self::compute_properties(&left, &right, Arc::clone(&schema));
+        let left_is_single_row = left
+            .statistics()
+            .is_ok_and(|stats| stats.num_rows == Precision::Exact(1));
+        let cache = Self::compute_properties(
+            &left,
+            &right,
+            Arc::clone(&schema),
+            left_is_single_row,
+        );
         CrossJoinExec {
             left,
             right,
             schema,
             left_fut: Default::default(),
             metrics: ExecutionPlanMetricsSet::default(),
+            left_is_single_row,
             cache,
         }
     }
@@ -99,21 +111,29 @@ impl CrossJoinExec {
         &self.right
     }
 
+    /// When the left side consists of exactly one row, every right side row
+    /// produces exactly one output row, so the right side ordering is
+    /// maintained.
+    fn maintains_input_order_impl(left_is_single_row: bool) -> Vec<bool> {
+        vec![false, left_is_single_row]
+    }
+
     /// This function creates the cache object that stores the plan properties such as schema, equivalence properties, ordering, partitioning, etc.
     fn compute_properties(
         left: &Arc<dyn ExecutionPlan>,
         right: &Arc<dyn ExecutionPlan>,
         schema: SchemaRef,
+        left_is_single_row: bool,
     ) -> PlanProperties {
         // Calculate equivalence properties
         // TODO: Check equivalence properties of cross join, it may preserve
-        //       ordering in some cases.
+        //       ordering in more cases.
         let eq_properties = join_equivalence_properties(
             left.equivalence_properties().clone(),
             right.equivalence_properties().clone(),
             &JoinType::Full,
             schema,
-            &[false, false],
+            &Self::maintains_input_order_impl(left_is_single_row),
             None,
             &[],
         );
@@ -226,6 +246,10 @@ impl ExecutionPlan for CrossJoinExec {
         ]
     }
 
+    fn maintains_input_order(&self) -> Vec<bool> {
+        Self::maintains_input_order_impl(self.left_is_single_row)
+    }
+
     fn execute(
         &self,
         partition: usize,
@@ -485,10 +509,13 @@ impl CrossJoinStream {
 mod tests {
     use super::*;
     use crate::common;
+    use crate::expressions::col;
+    use crate::memory::MemoryExec;
     use crate::test::build_table_scan_i32;
 
     use datafusion_common::{assert_batches_sorted_eq, assert_contains};
     use datafusion_execution::runtime_env::RuntimeEnvBuilder;
+    use datafusion_physical_expr::PhysicalSortExpr;
 
     async fn join_collect(
         left: Arc<dyn ExecutionPlan>,
@@ -700,6 +727,48 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_single_row_left_maintains_right_ordering() -> Result<()> {
+        let right = build_table_scan_i32(
+            ("a2", &vec![1, 2, 3]),
+            ("b2", &vec![4, 5, 6]),
+            ("c2", &vec![7, 8, 9]),
+        );
+        let right_schema = right.schema();
+        let right = Arc::new(
+            MemoryExec::try_new(&[vec![]], Arc::clone(&right_schema), None)?
+                .with_sort_information(vec![vec![PhysicalSortExpr {
+                    expr: col("a2", &right_schema)?,
+                    options: Default::default(),
+                }]]),
+        );
+
+        let single_row_left =
+            build_table_scan_i32(("a1", &vec![1]), ("b1", &vec![2]), ("c1", &vec![3]));
+        let join = CrossJoinExec::new(single_row_left, Arc::clone(&right) as _);
+        assert_eq!(join.maintains_input_order(), vec![false, true]);
+        assert_eq!(
+            join.properties().output_ordering(),
+            Some(
+                &[PhysicalSortExpr {
+                    expr: col("a2", &join.schema())?,
+                    options: Default::default(),
+                }][..]
+            )
+        );
+
+        let left = build_table_scan_i32(
+            ("a1", &vec![1, 2]),
+            ("b1", &vec![3, 4]),
+            ("c1", &vec![5, 6]),
+        );
+        let join = CrossJoinExec::new(left, right);
+        assert_eq!(join.maintains_input_order(), vec![false, false]);
+        assert_eq!(join.properties().output_ordering(), None);
+
+        Ok(())
+    }
+
     /// Returns the column names on the schema
     fn columns(schema: &Schema) -> Vec<String> {
         schema.fields().iter().map(|f| f.name().clone()).collect()
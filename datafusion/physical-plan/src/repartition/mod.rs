@@ -412,6 +412,14 @@ pub struct RepartitionExec {
     /// Boolean flag to decide whether to preserve ordering. If true means
     /// `SortPreservingRepartitionExec`, false means `RepartitionExec`.
     preserve_order: bool,
+    /// Batch size of the streaming merge of an order-preserving repartition.
+    /// `None` means the `batch_size` of the session config.
+    preserve_order_batch_size: Option<usize>,
+    /// Optional annotation displayed after the operator
+    annotation: Option<String>,
+    /// If true, an order-preserving repartition is reported under the name
+    /// `SortPreservingRepartitionExec`
+    sort_preserving_name: bool,
     /// Cache holding plan properties like equivalences, output partitioning etc.
     cache: PlanProperties,
 }
@@ -478,9 +486,20 @@ impl RepartitionExec {
         self.preserve_order
     }
 
+    /// Batch size of the streaming merge of an order-preserving repartition,
+    /// `None` means the `batch_size` of the session config
+    pub fn preserve_order_batch_size(&self) -> Option<usize> {
+        self.preserve_order_batch_size
+    }
+
+    /// Annotation displayed after the operator, if any
+    pub fn annotation(&self) -> Option<&str> {
+        self.annotation.as_deref()
+    }
+
     /// Get name used to display this Exec
     pub fn name(&self) -> &str {
-        "RepartitionExec"
+        ExecutionPlan::name(self)
     }
 }
 
@@ -500,7 +519,7 @@ impl DisplayAs for RepartitionExec {
                     self.input.output_partitioning().partition_count()
                 )?;
 
-                if self.preserve_order {
+                if self.preserve_order && !self.sort_preserving_name {
                     write!(f, ", preserve_order=true")?;
                 }
 
@@ -511,6 +530,14 @@ impl DisplayAs for RepartitionExec {
                         PhysicalSortExpr::format_list(sort_exprs)
                     )?;
                 }
+
+                if let Some(batch_size) = self.merge_batch_size() {
+                    write!(f, ", preserve_order_batch_size={batch_size}")?;
+                }
+
+                if let Some(annotation) = &self.annotation {
+                    write!(f, " ({annotation})")?;
+                }
                 Ok(())
             }
         }
@@ -519,7 +546,11 @@ impl DisplayAs for RepartitionExec {
 
 impl ExecutionPlan for RepartitionExec {
     fn name(&self) -> &'static str {
-        "RepartitionExec"
+        if self.preserve_order && self.sort_preserving_name {
+            "SortPreservingRepartitionExec"
+        } else {
+            "RepartitionExec"
+        }
     }
 
     /// Return a reference to Any that can be used for downcasting
@@ -544,7 +575,12 @@ impl ExecutionPlan for RepartitionExec {
         if self.preserve_order {
             repartition = repartition.with_preserve_order();
         }
-        Ok(Arc::new(repartition))
+        repartition.sort_preserving_name = self.sort_preserving_name;
+        Ok(Arc::new(
+            repartition
+                .with_preserve_order_batch_size(self.preserve_order_batch_size)
+                .with_annotation(self.annotation.clone()),
+        ))
     }
 
     fn benefits_from_input_partitioning(&self) -> Vec<bool> {
@@ -571,6 +607,7 @@ impl ExecutionPlan for RepartitionExec {
         let partitioning = self.partitioning.clone();
         let metrics = self.metrics.clone();
         let preserve_order = self.preserve_order;
+        let preserve_order_batch_size = self.preserve_order_batch_size;
         let name = self.name().to_owned();
         let schema = self.schema();
         let schema_captured = Arc::clone(&schema);
@@ -645,7 +682,8 @@ impl ExecutionPlan for RepartitionExec {
                     schema_captured,
                     &sort_exprs,
                     BaselineMetrics::new(&metrics, partition),
-                    context.session_config().batch_size(),
+                    preserve_order_batch_size
+                        .unwrap_or_else(|| context.session_config().batch_size()),
                     fetch,
                     merge_reservation,
                 )
@@ -691,6 +729,9 @@ impl RepartitionExec {
             state: Default::default(),
             metrics: ExecutionPlanMetricsSet::new(),
             preserve_order,
+            preserve_order_batch_size: None,
+            annotation: None,
+            sort_preserving_name: false,
             cache,
         })
     }
@@ -756,6 +797,38 @@ impl RepartitionExec {
         self
     }
 
+    /// Sets the batch size of the streaming merge of this repartition if it
+    /// preserves the order of its input (see [`Self::with_preserve_order`]).
+    /// Smaller batches reduce the peak memory of the merge, e.g. for wide
+    /// schemas. `None` means the `batch_size` of the session config.
+    pub fn with_preserve_order_batch_size(mut self, batch_size: Option<usize>) -> Self {
+        self.preserve_order_batch_size = batch_size;
+        self
+    }
+
+    /// Sets an annotation that is displayed after the operator, e.g. to tell
+    /// which optimizer rule introduced it (default is none)
+    pub fn with_annotation(mut self, annotation: Option<String>) -> Self {
+        self.annotation = annotation;
+        self
+    }
+
+    /// Reports this repartition under the name `SortPreservingRepartitionExec`
+    /// if it preserves the order of its input (see [`Self::with_preserve_order`]),
+    /// so that order-preserving repartitions are easier to match in plans. The
+    /// operator is still a `RepartitionExec` (default is false)
+    pub fn with_sort_preserving_name(mut self, sort_preserving_name: bool) -> Self {
+        self.sort_preserving_name = sort_preserving_name;
+        self
+    }
+
+    /// Return the batch size that overrides the session config for merging,
+    /// if this repartition preserves order
+    fn merge_batch_size(&self) -> Option<usize> {
+        self.preserve_order_batch_size
+            .filter(|_| self.preserve_order)
+    }
+
     /// Return the sort expressions that are used to merge
     fn sort_exprs(&self) -> Option<&[PhysicalSortExpr]> {
         if self.preserve_order {
@@ -1557,6 +1630,7 @@ mod tests {
 
 #[cfg(test)]
 mod test {
+    use arrow::array::UInt32Array;
     use arrow_schema::{DataType, Field, Schema, SortOptions};
 
     use datafusion_physical_expr::expressions::col;
@@ -1653,6 +1727,86 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_preserve_order_batch_size() -> Result<()> {
+        let schema = test_schema();
+        let sort_exprs = sort_exprs(&schema);
+        let source1 = sorted_memory_exec(&schema, sort_exprs.clone());
+        let source2 = sorted_memory_exec(&schema, sort_exprs.clone());
+        let union: Arc<dyn ExecutionPlan> =
+            Arc::new(UnionExec::new(vec![source1, source2]));
+        let exec = RepartitionExec::try_new(
+            Arc::clone(&union),
+            Partitioning::RoundRobinBatch(10),
+        )?
+        .with_preserve_order()
+        .with_preserve_order_batch_size(Some(64));
+
+        // The batch size is displayed
+        let expected_plan = [
+            "RepartitionExec: partitioning=RoundRobinBatch(10), input_partitions=2, preserve_order=true, sort_exprs=c0@0 ASC, preserve_order_batch_size=64",
+            "  UnionExec",
+            "    MemoryExec: partitions=1, partition_sizes=[0], output_ordering=c0@0 ASC",
+            "    MemoryExec: partitions=1, partition_sizes=[0], output_ordering=c0@0 ASC",
+        ];
+        assert_plan!(expected_plan, exec);
+
+        // The batch size is kept when the children are replaced
+        let exec = Arc::new(
+            RepartitionExec::try_new(
+                Arc::clone(&union),
+                Partitioning::RoundRobinBatch(10),
+            )?
+            .with_preserve_order()
+            .with_preserve_order_batch_size(Some(64)),
+        );
+        let exec = exec.with_new_children(vec![union])?;
+        let exec = exec.as_any().downcast_ref::<RepartitionExec>().unwrap();
+        assert_eq!(exec.preserve_order_batch_size(), Some(64));
+
+        // The batch size is not displayed if there is no merge to apply it to
+        let source = sorted_memory_exec(&schema, sort_exprs);
+        let exec = RepartitionExec::try_new(source, Partitioning::RoundRobinBatch(10))?
+            .with_preserve_order()
+            .with_preserve_order_batch_size(Some(64));
+        let expected_plan = [
+            "RepartitionExec: partitioning=RoundRobinBatch(10), input_partitions=1",
+            "  MemoryExec: partitions=1, partition_sizes=[0], output_ordering=c0@0 ASC",
+        ];
+        assert_plan!(expected_plan, exec);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_preserve_order_batch_size_execution() -> Result<()> {
+        let schema = test_schema();
+        let sort_exprs = sort_exprs(&schema);
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(UInt32Array::from((0..100).collect::<Vec<_>>()))],
+        )?;
+        let source = Arc::new(
+            MemoryExec::try_new(
+                &[vec![batch.clone()], vec![batch]],
+                Arc::clone(&schema),
+                None,
+            )?
+            .with_sort_information(vec![sort_exprs]),
+        );
+        let exec = RepartitionExec::try_new(source, Partitioning::RoundRobinBatch(1))?
+            .with_preserve_order()
+            .with_preserve_order_batch_size(Some(16));
+
+        // The merge emits batches of the configured size rather than the
+        // batch size of the session config
+        let task_ctx = Arc::new(TaskContext::default());
+        let batches = crate::common::collect(exec.execute(0, task_ctx)?).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 200);
+        assert!(batches.iter().all(|b| b.num_rows() <= 16));
+        assert!(batches.len() >= 200 / 16);
+        Ok(())
+    }
+
     fn test_schema() -> Arc<Schema> {
         Arc::new(Schema::new(vec![Field::new("c0", DataType::UInt32, false)]))
     }
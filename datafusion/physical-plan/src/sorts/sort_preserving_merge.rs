@@ -75,6 +75,10 @@ pub struct SortPreservingMergeExec {
     metrics: ExecutionPlanMetricsSet,
     /// Optional number of rows to fetch. Stops producing rows after this fetch
     fetch: Option<usize>,
+    /// Number of batches to buffer ahead for each input partition
+    prefetch: usize,
+    /// Optional annotation displayed after the operator
+    annotation: Option<String>,
     /// Cache holding plan properties like equivalences, output partitioning etc.
     cache: PlanProperties,
 }
@@ -88,6 +92,8 @@ impl SortPreservingMergeExec {
             expr,
             metrics: ExecutionPlanMetricsSet::new(),
             fetch: None,
+            prefetch: 1,
+            annotation: None,
             cache,
         }
     }
@@ -97,6 +103,21 @@ impl SortPreservingMergeExec {
         self
     }
 
+    /// Sets the number of batches to buffer ahead for each input partition.
+    /// Higher values reduce merge stalls at the cost of memory (default is 1).
+    /// A `prefetch` below 1 is treated as 1.
+    pub fn with_prefetch(mut self, prefetch: usize) -> Self {
+        self.prefetch = prefetch.max(1);
+        self
+    }
+
+    /// Sets an annotation that is displayed after the operator, e.g. to tell
+    /// which optimizer rule introduced it (default is none)
+    pub fn with_annotation(mut self, annotation: Option<String>) -> Self {
+        self.annotation = annotation;
+        self
+    }
+
     /// Input schema
     pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
         &self.input
@@ -112,6 +133,16 @@ impl SortPreservingMergeExec {
         self.fetch
     }
 
+    /// Number of batches buffered ahead for each input partition
+    pub fn prefetch(&self) -> usize {
+        self.prefetch
+    }
+
+    /// Annotation displayed after the operator, if any
+    pub fn annotation(&self) -> Option<&str> {
+        self.annotation.as_deref()
+    }
+
     /// This function creates the cache object that stores the plan properties such as schema, equivalence properties, ordering, partitioning, etc.
     fn compute_properties(
         input: &Arc<dyn ExecutionPlan>,
@@ -144,6 +175,9 @@ impl DisplayAs for SortPreservingMergeExec {
                 if let Some(fetch) = self.fetch {
                     write!(f, ", fetch={fetch}")?;
                 };
+                if let Some(annotation) = &self.annotation {
+                    write!(f, " ({annotation})")?;
+                }
 
                 Ok(())
             }
@@ -176,6 +210,8 @@ impl ExecutionPlan for SortPreservingMergeExec {
             expr: self.expr.clone(),
             metrics: self.metrics.clone(),
             fetch: limit,
+            prefetch: self.prefetch,
+            annotation: self.annotation.clone(),
             cache: self.cache.clone(),
         }))
     }
@@ -206,7 +242,9 @@ impl ExecutionPlan for SortPreservingMergeExec {
     ) -> Result<Arc<dyn ExecutionPlan>> {
         Ok(Arc::new(
             SortPreservingMergeExec::new(self.expr.clone(), Arc::clone(&children[0]))
-                .with_fetch(self.fetch),
+                .with_fetch(self.fetch)
+                .with_prefetch(self.prefetch)
+                .with_annotation(self.annotation.clone()),
         ))
     }
 
@@ -262,7 +300,7 @@ impl ExecutionPlan for SortPreservingMergeExec {
                     .map(|partition| {
                         let stream =
                             self.input.execute(partition, Arc::clone(&context))?;
-                        Ok(spawn_buffered(stream, 1))
+                        Ok(spawn_buffered(stream, self.prefetch))
                     })
                     .collect::<Result<_>>()?;
 
@@ -298,6 +336,195 @@ impl ExecutionPlan for SortPreservingMergeExec {
     }
 }
 
+/// Sort preserving merge of groups of partitions
+///
+/// Like [`SortPreservingMergeExec`], this takes an input execution plan whose
+/// partitions are sorted with respect to the given sort expressions. Instead
+/// of merging all partitions into one, it merges each group of (at most)
+/// `group_size` consecutive input partitions into one output partition. As each
+/// merged stream keeps a batch buffered, this bounds the memory used by a
+/// single merge; stacking these operators below a [`SortPreservingMergeExec`]
+/// forms a cascade of merges whose fan-ins stay within `group_size`.
+#[derive(Debug)]
+pub struct GroupedSortPreservingMergeExec {
+    /// Input plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Sort expressions
+    expr: Vec<PhysicalSortExpr>,
+    /// Maximum number of input partitions merged into one output partition
+    group_size: usize,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+    /// Cache holding plan properties like equivalences, output partitioning etc.
+    cache: PlanProperties,
+}
+
+impl GroupedSortPreservingMergeExec {
+    /// Create a new grouped merge execution plan. A `group_size` below 1 is
+    /// treated as 1.
+    pub fn new(
+        expr: Vec<PhysicalSortExpr>,
+        input: Arc<dyn ExecutionPlan>,
+        group_size: usize,
+    ) -> Self {
+        let group_size = group_size.max(1);
+        let cache = Self::compute_properties(&input, expr.clone(), group_size);
+        Self {
+            input,
+            expr,
+            group_size,
+            metrics: ExecutionPlanMetricsSet::new(),
+            cache,
+        }
+    }
+
+    /// Input schema
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+
+    /// Maximum number of input partitions merged into one output partition
+    pub fn group_size(&self) -> usize {
+        self.group_size
+    }
+
+    /// This function creates the cache object that stores the plan properties such as schema, equivalence properties, ordering, partitioning, etc.
+    fn compute_properties(
+        input: &Arc<dyn ExecutionPlan>,
+        ordering: Vec<PhysicalSortExpr>,
+        group_size: usize,
+    ) -> PlanProperties {
+        let mut eq_properties = input.equivalence_properties().clone();
+        eq_properties.clear_per_partition_constants();
+        eq_properties.add_new_orderings(vec![ordering]);
+        let input_partitions = input.output_partitioning().partition_count();
+        PlanProperties::new(
+            eq_properties,
+            Partitioning::UnknownPartitioning(input_partitions.div_ceil(group_size)),
+            input.execution_mode(),
+        )
+    }
+}
+
+impl DisplayAs for GroupedSortPreservingMergeExec {
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "GroupedSortPreservingMergeExec: [{}], group_size={}",
+                    PhysicalSortExpr::format_list(&self.expr),
+                    self.group_size
+                )
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for GroupedSortPreservingMergeExec {
+    fn name(&self) -> &'static str {
+        "GroupedSortPreservingMergeExec"
+    }
+
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.cache
+    }
+
+    fn required_input_distribution(&self) -> Vec<Distribution> {
+        vec![Distribution::UnspecifiedDistribution]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn required_input_ordering(&self) -> Vec<Option<LexRequirement>> {
+        vec![Some(PhysicalSortRequirement::from_sort_exprs(&self.expr))]
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(GroupedSortPreservingMergeExec::new(
+            self.expr.clone(),
+            Arc::clone(&children[0]),
+            self.group_size,
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        trace!(
+            "Start GroupedSortPreservingMergeExec::execute for partition: {}",
+            partition
+        );
+        let input_partitions = self.input.output_partitioning().partition_count();
+        let start = partition * self.group_size;
+        let end = input_partitions.min(start + self.group_size);
+        if start >= end {
+            return internal_err!(
+                "GroupedSortPreservingMergeExec invalid partition {partition}"
+            );
+        }
+        if end - start == 1 {
+            return self.input.execute(start, context);
+        }
+
+        let reservation =
+            MemoryConsumer::new(format!("GroupedSortPreservingMergeExec[{partition}]"))
+                .register(&context.runtime_env().memory_pool);
+        let receivers = (start..end)
+            .map(|input_partition| {
+                let stream = self.input.execute(input_partition, Arc::clone(&context))?;
+                Ok(spawn_buffered(stream, 1))
+            })
+            .collect::<Result<_>>()?;
+        streaming_merge(
+            receivers,
+            self.schema(),
+            &self.expr,
+            BaselineMetrics::new(&self.metrics, partition),
+            context.session_config().batch_size(),
+            None,
+            reservation,
+        )
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        self.input.statistics()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fmt::Formatter;
@@ -381,6 +608,47 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_grouped_merge() -> Result<()> {
+        let task_ctx = Arc::new(TaskContext::default());
+        let partitions = [vec![1, 4], vec![2, 5], vec![3, 6], vec![0, 7], vec![8, 9]]
+            .into_iter()
+            .map(|values| {
+                let a: ArrayRef = Arc::new(Int32Array::from(values));
+                Ok(vec![RecordBatch::try_from_iter(vec![("a", a)])?])
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let schema = partitions[0][0].schema();
+        let sort = vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions::default(),
+        }];
+        let exec = MemoryExec::try_new(&partitions, schema, None)?;
+        let merge =
+            Arc::new(GroupedSortPreservingMergeExec::new(sort, Arc::new(exec), 2));
+        assert_eq!(
+            merge.properties().output_partitioning().partition_count(),
+            3
+        );
+
+        let expected = [
+            vec![
+                "+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 4 |", "| 5 |", "+---+",
+            ],
+            vec![
+                "+---+", "| a |", "+---+", "| 0 |", "| 3 |", "| 6 |", "| 7 |", "+---+",
+            ],
+            vec!["+---+", "| a |", "+---+", "| 8 |", "| 9 |", "+---+"],
+        ];
+        for (partition, expected) in expected.iter().enumerate() {
+            let stream = merge.execute(partition, Arc::clone(&task_ctx))?;
+            let batches = common::collect(stream).await?;
+            assert_batches_eq!(expected, &batches);
+        }
+        assert!(merge.execute(3, task_ctx).is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_merge_no_exprs() {
         let task_ctx = Arc::new(TaskContext::default());
@@ -878,6 +1146,33 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_merge_without_prefetch() -> Result<()> {
+        let task_ctx = Arc::new(TaskContext::default());
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 3]));
+        let batch1 = RecordBatch::try_from_iter(vec![("a", a)])?;
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![2, 4]));
+        let batch2 = RecordBatch::try_from_iter(vec![("a", a)])?;
+        let schema = batch1.schema();
+
+        let sort = vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions::default(),
+        }];
+        let exec = MemoryExec::try_new(&[vec![batch1], vec![batch2]], schema, None)?;
+        // A prefetch of 0 would create channels without capacity, so at least
+        // one batch is buffered ahead:
+        let merge = SortPreservingMergeExec::new(sort, Arc::new(exec)).with_prefetch(0);
+        assert_eq!(merge.prefetch(), 1);
+
+        let collected = collect(Arc::new(merge), task_ctx).await?;
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 3 |", "| 4 |", "+---+",],
+            collected.as_slice()
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_sort_merge_single_partition_without_fetch() {
         let task_ctx = Arc::new(TaskContext::default());
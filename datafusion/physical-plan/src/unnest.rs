@@ -43,7 +43,8 @@ use datafusion_common::{
 };
 use datafusion_execution::TaskContext;
 use datafusion_expr::ColumnarValue;
-use datafusion_physical_expr::EquivalenceProperties;
+use datafusion_physical_expr::equivalence::ProjectionMapping;
+use datafusion_physical_expr::expressions::Column;
 
 use async_trait::async_trait;
 use futures::{Stream, StreamExt};
@@ -83,7 +84,12 @@ impl UnnestExec {
         schema: SchemaRef,
         options: UnnestOptions,
     ) -> Self {
-        let cache = Self::compute_properties(&input, Arc::clone(&schema));
+        let cache = Self::compute_properties(
+            &input,
+            Arc::clone(&schema),
+            &list_column_indices,
+            &struct_column_indices,
+        );
 
         UnnestExec {
             input,
@@ -100,8 +106,35 @@ impl UnnestExec {
     fn compute_properties(
         input: &Arc<dyn ExecutionPlan>,
         schema: SchemaRef,
+        list_column_indices: &[usize],
+        struct_column_indices: &[usize],
     ) -> PlanProperties {
-        let eq_properties = EquivalenceProperties::new(schema);
+        // Unnesting keeps the relative order of the input rows, so orderings
+        // (and other equivalences) on the columns that are not unnested carry
+        // over to the output. Struct columns are expanded into their fields,
+        // which shifts the columns after them:
+        let input_schema = input.schema();
+        let mut output_idx = 0;
+        let mut map = vec![];
+        for (input_idx, field) in input_schema.fields().iter().enumerate() {
+            if struct_column_indices.contains(&input_idx) {
+                if let DataType::Struct(fields) = field.data_type() {
+                    output_idx += fields.len();
+                }
+                continue;
+            }
+            if !list_column_indices.contains(&input_idx) {
+                let source = Arc::new(Column::new(field.name(), input_idx)) as _;
+                let target =
+                    Arc::new(Column::new(schema.field(output_idx).name(), output_idx))
+                        as _;
+                map.push((source, target));
+            }
+            output_idx += 1;
+        }
+        let eq_properties = input
+            .equivalence_properties()
+            .project(&ProjectionMapping { map }, schema);
 
         PlanProperties::new(
             eq_properties,
@@ -178,6 +211,10 @@ impl ExecutionPlan for UnnestExec {
         vec![Distribution::UnspecifiedDistribution]
     }
 
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
     fn execute(
         &self,
         partition: usize,
@@ -679,9 +716,12 @@ fn flatten_list_cols_from_indices(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use arrow::datatypes::Field;
+    use crate::memory::MemoryExec;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{Field, Fields};
     use arrow_array::{GenericListArray, OffsetSizeTrait, StringArray};
     use arrow_buffer::{BooleanBufferBuilder, NullBuffer, OffsetBuffer};
+    use datafusion_physical_expr::PhysicalSortExpr;
 
     // Create a GenericListArray with the following list values:
     //  [A, B, C], [], NULL, [D], NULL, [NULL, F]
@@ -868,4 +908,46 @@ mod tests {
         assert_eq!(take_indicies, expected);
         Ok(())
     }
+
+    #[test]
+    fn test_unnest_maintains_ordering_of_other_columns() -> datafusion_common::Result<()>
+    {
+        let struct_fields = Fields::from(vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Int32, true),
+        ]);
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("s", DataType::Struct(struct_fields), true),
+            Field::new_list("l", Field::new_list_field(DataType::Int32, true), true),
+            Field::new("a", DataType::Int32, true),
+        ]));
+        let ordering = vec![PhysicalSortExpr::new(
+            Arc::new(Column::new("a", 2)),
+            SortOptions::default(),
+        )];
+        let input = Arc::new(
+            MemoryExec::try_new(&[vec![]], Arc::clone(&input_schema), None)?
+                .with_sort_information(vec![ordering]),
+        );
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Int32, true),
+            Field::new("l", DataType::Int32, true),
+            Field::new("a", DataType::Int32, true),
+        ]));
+        let unnest =
+            UnnestExec::new(input, vec![1], vec![0], schema, UnnestOptions::default());
+
+        // The struct column expands into two columns, so `a` moves to index 3:
+        let expected = vec![PhysicalSortExpr::new(
+            Arc::new(Column::new("a", 3)),
+            SortOptions::default(),
+        )];
+        assert_eq!(
+            unnest.properties().output_ordering(),
+            Some(expected.as_slice())
+        );
+        assert_eq!(unnest.maintains_input_order(), vec![true]);
+        Ok(())
+    }
 }
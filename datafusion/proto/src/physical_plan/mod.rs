@@ -56,7 +56,9 @@ use datafusion::physical_plan::placeholder_row::PlaceholderRowExec;
 use datafusion::physical_plan::projection::ProjectionExec;
 use datafusion::physical_plan::repartition::RepartitionExec;
 use datafusion::physical_plan::sorts::sort::SortExec;
-use datafusion::physical_plan::sorts::sort_preserving_merge::SortPreservingMergeExec;
+use datafusion::physical_plan::sorts::sort_preserving_merge::{
+    GroupedSortPreservingMergeExec, SortPreservingMergeExec,
+};
 use datafusion::physical_plan::union::{InterleaveExec, UnionExec};
 use datafusion::physical_plan::unnest::UnnestExec;
 use datafusion::physical_plan::windows::{BoundedWindowAggExec, WindowAggExec};
@@ -299,10 +301,14 @@ impl AsExecutionPlan for protobuf::PhysicalPlanNode {
                     input.schema().as_ref(),
                     extension_codec,
                 )?;
-                Ok(Arc::new(RepartitionExec::try_new(
-                    input,
-                    partitioning.unwrap(),
-                )?))
+                Ok(Arc::new(
+                    RepartitionExec::try_new(input, partitioning.unwrap())?
+                        .with_preserve_order_batch_size(
+                            repart
+                                .preserve_order_batch_size
+                                .map(|batch_size| batch_size as usize),
+                        ),
+                ))
             }
             PhysicalPlanType::GlobalLimit(limit) => {
                 let input: Arc<dyn ExecutionPlan> =
@@ -919,9 +925,54 @@ impl AsExecutionPlan for protobuf::PhysicalPlanNode {
                 } else {
                     Some(sort.fetch as usize)
                 };
-                Ok(Arc::new(
-                    SortPreservingMergeExec::new(exprs, input).with_fetch(fetch),
-                ))
+                let mut merge =
+                    SortPreservingMergeExec::new(exprs, input).with_fetch(fetch);
+                if let Some(prefetch) = sort.prefetch {
+                    merge = merge.with_prefetch(prefetch as usize);
+                }
+                Ok(Arc::new(merge))
+            }
+            PhysicalPlanType::GroupedSortPreservingMerge(sort) => {
+                let input: Arc<dyn ExecutionPlan> =
+                    into_physical_plan(&sort.input, registry, runtime, extension_codec)?;
+                let exprs = sort
+                    .expr
+                    .iter()
+                    .map(|expr| {
+                        let expr = expr.expr_type.as_ref().ok_or_else(|| {
+                            proto_error(format!(
+                                "physical_plan::from_proto() Unexpected expr {self:?}"
+                            ))
+                        })?;
+                        if let protobuf::physical_expr_node::ExprType::Sort(sort_expr) = expr {
+                            let expr = sort_expr
+                                .expr
+                                .as_ref()
+                                .ok_or_else(|| {
+                                    proto_error(format!(
+                                        "physical_plan::from_proto() Unexpected sort expr {self:?}"
+                                    ))
+                                })?
+                                .as_ref();
+                            Ok(PhysicalSortExpr {
+                                expr: parse_physical_expr(expr, registry, input.schema().as_ref(), extension_codec)?,
+                                options: SortOptions {
+                                    descending: !sort_expr.asc,
+                                    nulls_first: sort_expr.nulls_first,
+                                },
+                            })
+                        } else {
+                            internal_err!(
+                                "physical_plan::from_proto() {self:?}"
+                            )
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Arc::new(GroupedSortPreservingMergeExec::new(
+                    exprs,
+                    input,
+                    sort.group_size as usize,
+                )))
             }
             PhysicalPlanType::Extension(extension) => {
                 let inputs: Vec<Arc<dyn ExecutionPlan>> = extension
@@ -1665,6 +1716,9 @@ impl AsExecutionPlan for protobuf::PhysicalPlanNode {
                     protobuf::RepartitionExecNode {
                         input: Some(Box::new(input)),
                         partitioning: Some(pb_partitioning),
+                        preserve_order_batch_size: exec
+                            .preserve_order_batch_size()
+                            .map(|batch_size| batch_size as u64),
                     },
                 ))),
             });
@@ -1769,6 +1823,42 @@ impl AsExecutionPlan for protobuf::PhysicalPlanNode {
                         input: Some(Box::new(input)),
                         expr,
                         fetch: exec.fetch().map(|f| f as i64).unwrap_or(-1),
+                        prefetch: Some(exec.prefetch() as u64),
+                    }),
+                )),
+            });
+        }
+
+        if let Some(exec) = plan.downcast_ref::<GroupedSortPreservingMergeExec>() {
+            let input = protobuf::PhysicalPlanNode::try_from_physical_plan(
+                exec.input().to_owned(),
+                extension_codec,
+            )?;
+            let expr = exec
+                .expr()
+                .iter()
+                .map(|expr| {
+                    let sort_expr = Box::new(protobuf::PhysicalSortExprNode {
+                        expr: Some(Box::new(serialize_physical_expr(
+                            &expr.expr,
+                            extension_codec,
+                        )?)),
+                        asc: !expr.options.descending,
+                        nulls_first: expr.options.nulls_first,
+                    });
+                    Ok(protobuf::PhysicalExprNode {
+                        expr_type: Some(protobuf::physical_expr_node::ExprType::Sort(
+                            sort_expr,
+                        )),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(protobuf::PhysicalPlanNode {
+                physical_plan_type: Some(PhysicalPlanType::GroupedSortPreservingMerge(
+                    Box::new(protobuf::GroupedSortPreservingMergeExecNode {
+                        input: Some(Box::new(input)),
+                        expr,
+                        group_size: exec.group_size() as u64,
                     }),
                 )),
             });
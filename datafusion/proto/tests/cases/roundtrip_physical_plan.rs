@@ -70,6 +70,9 @@ use datafusion::physical_plan::placeholder_row::PlaceholderRowExec;
 use datafusion::physical_plan::projection::ProjectionExec;
 use datafusion::physical_plan::repartition::RepartitionExec;
 use datafusion::physical_plan::sorts::sort::SortExec;
+use datafusion::physical_plan::sorts::sort_preserving_merge::{
+    GroupedSortPreservingMergeExec, SortPreservingMergeExec,
+};
 use datafusion::physical_plan::udaf::AggregateFunctionExpr;
 use datafusion::physical_plan::union::{InterleaveExec, UnionExec};
 use datafusion::physical_plan::unnest::UnnestExec;
@@ -632,6 +635,88 @@ fn roundtrip_sort_preserve_partitioning() -> Result<()> {
     ))
 }
 
+#[test]
+fn roundtrip_sort_preserving_merge() -> Result<()> {
+    let field_a = Field::new("a", DataType::Boolean, false);
+    let field_b = Field::new("b", DataType::Int64, false);
+    let schema = Arc::new(Schema::new(vec![field_a, field_b]));
+    let sort_exprs = vec![
+        PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        },
+        PhysicalSortExpr {
+            expr: col("b", &schema)?,
+            options: SortOptions {
+                descending: false,
+                nulls_first: true,
+            },
+        },
+    ];
+    let input = Arc::new(RepartitionExec::try_new(
+        Arc::new(EmptyExec::new(schema)),
+        Partitioning::RoundRobinBatch(8),
+    )?);
+
+    roundtrip_test(Arc::new(
+        SortPreservingMergeExec::new(sort_exprs.clone(), Arc::clone(&input) as _)
+            .with_fetch(Some(10)),
+    ))?;
+    roundtrip_test(Arc::new(
+        SortPreservingMergeExec::new(sort_exprs, input).with_prefetch(4),
+    ))
+}
+
+#[test]
+fn roundtrip_grouped_sort_preserving_merge() -> Result<()> {
+    let field_a = Field::new("a", DataType::Boolean, false);
+    let field_b = Field::new("b", DataType::Int64, false);
+    let schema = Arc::new(Schema::new(vec![field_a, field_b]));
+    let sort_exprs = vec![
+        PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        },
+        PhysicalSortExpr {
+            expr: col("b", &schema)?,
+            options: SortOptions {
+                descending: false,
+                nulls_first: true,
+            },
+        },
+    ];
+    let input = Arc::new(RepartitionExec::try_new(
+        Arc::new(EmptyExec::new(schema)),
+        Partitioning::RoundRobinBatch(8),
+    )?);
+
+    roundtrip_test(Arc::new(GroupedSortPreservingMergeExec::new(
+        sort_exprs, input, 3,
+    )))
+}
+
+#[test]
+fn roundtrip_repartition_with_preserve_order_batch_size() -> Result<()> {
+    let field_a = Field::new("a", DataType::Int64, false);
+    let schema = Arc::new(Schema::new(vec![field_a]));
+    let input = Arc::new(RepartitionExec::try_new(
+        Arc::new(EmptyExec::new(Arc::clone(&schema))),
+        Partitioning::RoundRobinBatch(8),
+    )?);
+    let partitioning = Partitioning::Hash(vec![col("a", &schema)?], 4);
+
+    roundtrip_test(Arc::new(
+        RepartitionExec::try_new(input, partitioning)?
+            .with_preserve_order_batch_size(Some(128)),
+    ))
+}
+
 #[test]
 fn roundtrip_coalesce_with_fetch() -> Result<()> {
     let field_a = Field::new("a", DataType::Boolean, false);